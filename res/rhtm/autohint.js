@@ -74,6 +74,8 @@ $(document).ready(function() {
 		var lookup;
 		if (chkreg.checked) {
 			lookup = "/s/%5E" + encodeURIComponent(qword.val()) + "%24";
+		} else if (/[*?]/.test(qword.val())) {
+			lookup = "/g/" + encodeURIComponent(qword.val());
 		} else {
 			lookup = "/W/" + encodeURIComponent(qword.val());
 		}
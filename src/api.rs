@@ -0,0 +1,236 @@
+use regex::bytes::Regex;
+use std::str;
+
+use super::json;
+use super::reformat::ContentReformat;
+use stardict::{Entry, Ifo, StarDict, WordMergeIter};
+
+/// serve the JSON api. `req` is the url after `/api/`, like `lookup/WORD`.
+/// supported requests:
+/// 1. `lookup/WORD`: the definitions of WORD, with raw and reformatted content of each field,
+///    or the size of a binary field.
+///    `match` is "headword", "synonym", or "case_folded" if the word only matches WORD in
+///    another case, like "Polish" of "polish". `synonym` is the synonym which matched, if any.
+///    `synonyms` are all synonyms of the word, its alternate forms like "colour" of "color".
+///    `approximate` is true if the word only matches WORD without accents or case, etc.
+///    `base_form` is the base form looked up if WORD is an inflection not found, like "study".
+/// 2. `neighbors/WORD?o=OFFSET&l=LENGTH`: the neighbor words, with their dictionary.
+/// 3. `search/REGEX?l=LENGTH`: the words matching REGEX, with their dictionary.
+///    `glob/PATTERN?l=LENGTH` is the same, with wildcards `*` and `?` instead of REGEX.
+///    `text/WORDS?l=LENGTH` searches the definitions for WORDS, each hit with a snippet.
+/// 4. `suggest/WORD?l=LENGTH`: the words within `max_dist` edits of WORD, nearest first.
+/// 5. `info`: the metadata of all dictionaries.
+///
+/// nothing is written if the request is unknown.
+pub fn handle(
+    dict: &StarDict,
+    cr: &ContentReformat,
+    req: &[u8],
+    offset: i32,
+    length: usize,
+    max_dist: usize,
+    content: &mut Vec<u8>,
+) {
+    let mut it = req.splitn(2, |c| *c == b'/');
+    let cmd = it.next().unwrap_or(b"");
+    let word = it.next().unwrap_or(b"");
+    match cmd {
+        b"lookup" if !word.is_empty() => lookup(dict, cr, word, content),
+        b"neighbors" if !word.is_empty() => neighbors(dict, word, offset, length, content),
+        b"search" if !word.is_empty() => search(dict, word, false, length, content),
+        b"glob" if !word.is_empty() => search(dict, word, true, length, content),
+        b"text" if !word.is_empty() => text(dict, word, length, content),
+        b"suggest" if !word.is_empty() => suggest(dict, word, max_dist, length, content),
+        b"info" => info(dict, content),
+        _ => (),
+    }
+}
+
+fn error(msg: &str, content: &mut Vec<u8>) {
+    content.push(b'{');
+    json::push_pair(content, "error", msg.as_bytes());
+    content.push(b'}');
+}
+
+fn lookup(dict: &StarDict, cr: &ContentReformat, word: &[u8], content: &mut Vec<u8>) {
+    let x = match dict.lookup(word) {
+        Ok(x) => x,
+        Err(e) => return error(&e.to_string(), content),
+    };
+    content.push(b'[');
+    for (i, e) in x.iter().enumerate() {
+        if i > 0 {
+            content.push(b',');
+        }
+        content.push(b'{');
+        json::push_pair(content, "dictionary", e.dictionary.name.as_bytes());
+        content.push(b',');
+        json::push_pair(content, "word", &e.word);
+        content.push(b',');
+        json::push_pair(content, "match", e.kind.as_str().as_bytes());
+        content.push(b',');
+        if let Some(s) = &e.synonym {
+            json::push_pair(content, "synonym", s);
+            content.push(b',');
+        }
+        json::push_key(content, "synonyms");
+        content.push(b'[');
+        for (i, s) in e.synonyms.iter().enumerate() {
+            if i > 0 {
+                content.push(b',');
+            }
+            json::push_str(content, s);
+        }
+        content.extend(b"],");
+        json::push_bool(content, "approximate", e.approximate);
+        content.push(b',');
+        if let Some(b) = &e.base_form {
+            json::push_pair(content, "base_form", b);
+            content.push(b',');
+        }
+        json::push_pair(
+            content,
+            "same_type_sequence",
+            e.dictionary.same_type_sequence.as_bytes(),
+        );
+        content.push(b',');
+        json::push_key(content, "fields");
+        content.push(b'[');
+        for (j, (a, b)) in e.entry.fields.iter().enumerate() {
+            if j > 0 {
+                content.push(b',');
+            }
+            content.push(b'{');
+            json::push_pair(content, "type", &[*a]);
+            content.push(b',');
+            if Entry::is_binary(*a) {
+                // binary data is not JSON text.
+                json::push_num(content, "size", b.len() as u64);
+            } else {
+                json::push_pair(content, "raw", b);
+                content.push(b',');
+                json::push_pair(
+                    content,
+                    "html",
+                    &cr.replace_all(*a, e.dictionary.dict_path.as_bytes(), b),
+                );
+            }
+            content.push(b'}');
+        }
+        content.extend(b"]}");
+    }
+    content.push(b']');
+}
+
+/// write `[{"word":..., "dictionary":...}, ...]` from a word merge iterator.
+fn word_list<'a, T: Iterator<Item = &'a [u8]>>(
+    dict: &StarDict,
+    mut it: WordMergeIter<'a, T>,
+    length: usize,
+    content: &mut Vec<u8>,
+) {
+    content.push(b'[');
+    let mut n = 0usize;
+    while n < length {
+        let (src, w) = match it.next_with_source() {
+            Some(x) => x,
+            None => break,
+        };
+        if n > 0 {
+            content.push(b',');
+        }
+        content.push(b'{');
+        json::push_pair(content, "word", w);
+        if let Some(ifo) = dict.source_info(src) {
+            content.push(b',');
+            json::push_pair(content, "dictionary", ifo.name.as_bytes());
+        }
+        content.push(b'}');
+        n += 1;
+    }
+    content.push(b']');
+}
+
+fn neighbors(dict: &StarDict, word: &[u8], offset: i32, length: usize, content: &mut Vec<u8>) {
+    word_list(dict, dict.neighbors(word, offset), length, content);
+}
+
+fn search(dict: &StarDict, word: &[u8], glob: bool, length: usize, content: &mut Vec<u8>) {
+    let reg = match str::from_utf8(word).map(|w| {
+        if glob {
+            stardict::glob_regex(w)
+        } else {
+            Regex::new(w)
+        }
+    }) {
+        Ok(Ok(r)) => r,
+        Ok(Err(e)) => return error(&e.to_string(), content),
+        Err(e) => return error(&e.to_string(), content),
+    };
+    word_list(dict, dict.search(&reg), length, content);
+}
+
+fn text(dict: &StarDict, word: &[u8], length: usize, content: &mut Vec<u8>) {
+    content.push(b'[');
+    for (i, e) in dict.search_text(word, length).iter().enumerate() {
+        if i > 0 {
+            content.push(b',');
+        }
+        content.push(b'{');
+        json::push_pair(content, "word", e.word);
+        content.push(b',');
+        json::push_pair(content, "dictionary", e.dictionary.name.as_bytes());
+        content.push(b',');
+        json::push_pair(content, "snippet", e.snippet.as_bytes());
+        content.push(b'}');
+    }
+    content.push(b']');
+}
+
+fn suggest(dict: &StarDict, word: &[u8], max_dist: usize, length: usize, content: &mut Vec<u8>) {
+    content.push(b'[');
+    for (i, w) in dict.suggest(word, max_dist, length).iter().enumerate() {
+        if i > 0 {
+            content.push(b',');
+        }
+        json::push_str(content, w);
+    }
+    content.push(b']');
+}
+
+fn info(dict: &StarDict, content: &mut Vec<u8>) {
+    content.push(b'[');
+    for (i, d) in dict.info().iter().enumerate() {
+        if i > 0 {
+            content.push(b',');
+        }
+        info_one(d, content);
+    }
+    content.push(b']');
+}
+
+fn info_one(d: &Ifo, content: &mut Vec<u8>) {
+    content.push(b'{');
+    json::push_pair(content, "name", d.name.as_bytes());
+    content.push(b',');
+    json::push_pair(content, "author", d.author.as_bytes());
+    content.push(b',');
+    json::push_pair(content, "description", d.description.as_bytes());
+    content.push(b',');
+    json::push_pair(
+        content,
+        "same_type_sequence",
+        d.same_type_sequence.as_bytes(),
+    );
+    content.push(b',');
+    json::push_pair(content, "dict_path", d.dict_path.as_bytes());
+    content.push(b',');
+    json::push_num(content, "idx_file_size", d.idx_file_size as u64);
+    content.push(b',');
+    json::push_num(content, "word_count", d.word_count as u64);
+    content.push(b',');
+    json::push_num(content, "syn_word_count", d.syn_word_count as u64);
+    content.push(b',');
+    json::push_num(content, "idxoffsetbits", d.idxoffsetbits as u64);
+    content.push(b'}');
+}
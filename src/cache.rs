@@ -0,0 +1,222 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path;
+use std::time::UNIX_EPOCH;
+
+use super::result::DictError;
+
+const MAGIC: &[u8; 8] = b"SDOFT002";
+
+/// the parsed word table of an .idx or .syn file, persisted to a sidecar `.oft` file
+/// so that the next start does not need to parse the whole file again.
+/// file format(all integers are u32/u64 le):
+/// 1. magic "SDOFT002".
+/// 2. source file size u64, mtime seconds u64, mtime nanoseconds u32, and the bytes
+///    after each word u32(the offset and length of .idx, or the index of .syn).
+/// 3. word count u32, then first, middle and last word, each as length u32 and bytes.
+/// 4. word count * u32, the end of each word.
+pub struct OffsetCache {
+    pub index: Vec<u32>,
+    pub firstword: Vec<u8>,
+    pub middleword: Vec<u8>,
+    pub lastword: Vec<u8>,
+}
+
+/// the identity of the source file, the cache is stale if any of it changes.
+pub(crate) fn stamp(file: &path::Path) -> Result<(u64, u64, u32), DictError> {
+    let meta = fs::metadata(file)?;
+    let mtime = meta
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| DictError::My(format!("bad mtime: {}", e)))?;
+    Ok((meta.len(), mtime.as_secs(), mtime.subsec_nanos()))
+}
+
+/// `XYZ.idx` is cached in `XYZ.idx.oft`.
+fn cache_path(file: &path::Path) -> path::PathBuf {
+    let mut p = file.as_os_str().to_owned();
+    p.push(".oft");
+    path::PathBuf::from(p)
+}
+
+pub(crate) fn read_u32(r: &mut impl Read) -> Result<u32, DictError> {
+    let mut b = [0u8; 4];
+    r.read_exact(&mut b)?;
+    Ok(u32::from_le_bytes(b))
+}
+pub(crate) fn read_u64(r: &mut impl Read) -> Result<u64, DictError> {
+    let mut b = [0u8; 8];
+    r.read_exact(&mut b)?;
+    Ok(u64::from_le_bytes(b))
+}
+pub(crate) fn read_word(r: &mut impl Read) -> Result<Vec<u8>, DictError> {
+    let mut w = vec![0u8; read_u32(r)? as usize];
+    r.read_exact(&mut w)?;
+    Ok(w)
+}
+
+impl OffsetCache {
+    /// load the cache of `file`. Err(DictError) if there is no cache, or it is
+    /// stale, or it does not hold `count` words, each followed by `off_len_bytes`,
+    /// within `data_len` bytes.
+    pub fn load(
+        file: &path::Path,
+        count: usize,
+        off_len_bytes: usize,
+        data_len: usize,
+    ) -> Result<OffsetCache, DictError> {
+        let (size, secs, nanos) = stamp(file)?;
+        let mut r = BufReader::new(File::open(cache_path(file))?);
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC
+            || read_u64(&mut r)? != size
+            || read_u64(&mut r)? != secs
+            || read_u32(&mut r)? != nanos
+            || read_u32(&mut r)? as usize != off_len_bytes
+            || read_u32(&mut r)? as usize != count
+        {
+            return Err(DictError::My(String::from("stale offset cache")));
+        }
+        let firstword = read_word(&mut r)?;
+        let middleword = read_word(&mut r)?;
+        let lastword = read_word(&mut r)?;
+        let mut index = Vec::with_capacity(count);
+        let mut buf = [0u8; 4];
+        for _ in 0..count {
+            r.read_exact(&mut buf)?;
+            index.push(u32::from_le_bytes(buf));
+        }
+        // a broken cache would make get_word() and get_offset_length() slice out of
+        // range: each word ends with '\0' and is followed by `off_len_bytes`, then the
+        // next word begins.
+        let gap = 1 + off_len_bytes;
+        if index.last().is_some_and(|l| *l as usize + gap > data_len)
+            || index
+                .windows(2)
+                .any(|w| w[0] as usize + gap > w[1] as usize)
+        {
+            return Err(DictError::My(String::from("bad offset cache")));
+        }
+        Ok(OffsetCache {
+            index,
+            firstword,
+            middleword,
+            lastword,
+        })
+    }
+    /// write the cache of `file`, with `off_len_bytes` after each word.
+    /// failure is not fatal, the caller may ignore it.
+    pub fn save(&self, file: &path::Path, off_len_bytes: usize) -> Result<(), DictError> {
+        let (size, secs, nanos) = stamp(file)?;
+        let cfile = cache_path(file);
+        // a server and a command line run may write the same cache at the same time.
+        let mut tmp = cfile.clone().into_os_string();
+        tmp.push(format!(".{}.tmp", std::process::id()));
+        {
+            let mut w = BufWriter::new(File::create(&tmp)?);
+            w.write_all(MAGIC)?;
+            w.write_all(&size.to_le_bytes())?;
+            w.write_all(&secs.to_le_bytes())?;
+            w.write_all(&nanos.to_le_bytes())?;
+            w.write_all(&(off_len_bytes as u32).to_le_bytes())?;
+            w.write_all(&(self.index.len() as u32).to_le_bytes())?;
+            for word in [&self.firstword, &self.middleword, &self.lastword].iter() {
+                w.write_all(&(word.len() as u32).to_le_bytes())?;
+                w.write_all(word)?;
+            }
+            for i in self.index.iter() {
+                w.write_all(&i.to_le_bytes())?;
+            }
+            w.flush()?;
+        }
+        // rename so that a concurrent reader never sees a half written cache.
+        fs::rename(&tmp, &cfile)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    // two words "a" and "bc", each followed by 8 bytes.
+    const DATA: &[u8] = b"a\0........bc\0........";
+
+    fn source(name: &str) -> path::PathBuf {
+        let file =
+            std::env::temp_dir().join(format!("stardict-{}-{}.idx", std::process::id(), name));
+        fs::write(&file, DATA).unwrap();
+        file
+    }
+    fn cache(index: Vec<u32>) -> OffsetCache {
+        OffsetCache {
+            index,
+            firstword: b"a".to_vec(),
+            middleword: b"bc".to_vec(),
+            lastword: b"bc".to_vec(),
+        }
+    }
+    fn remove(file: &path::Path) {
+        fs::remove_file(cache_path(file)).unwrap();
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn load_saved() {
+        let file = source("saved");
+        cache(vec![1, 12]).save(&file, 8).unwrap();
+        let c = OffsetCache::load(&file, 2, 8, DATA.len()).unwrap();
+        assert_eq!(c.index, vec![1, 12]);
+        assert_eq!(c.lastword, b"bc");
+        // the count and the entry width must be the same.
+        assert!(OffsetCache::load(&file, 3, 8, DATA.len()).is_err());
+        assert!(OffsetCache::load(&file, 2, 12, DATA.len()).is_err());
+        remove(&file);
+    }
+
+    #[test]
+    fn reject_changed_source() {
+        let file = source("changed");
+        cache(vec![1, 12]).save(&file, 8).unwrap();
+        // the same size, only the modified time changes.
+        let later = SystemTime::now() + Duration::from_secs(10);
+        File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(OffsetCache::load(&file, 2, 8, DATA.len()).is_err());
+
+        cache(vec![1, 12]).save(&file, 8).unwrap();
+        fs::write(&file, b"a\0........").unwrap();
+        assert!(OffsetCache::load(&file, 2, 8, DATA.len()).is_err());
+        remove(&file);
+    }
+
+    #[test]
+    fn reject_truncated_cache() {
+        let file = source("truncated");
+        cache(vec![1, 12]).save(&file, 8).unwrap();
+        let c = fs::read(cache_path(&file)).unwrap();
+        fs::write(cache_path(&file), &c[..c.len() - 2]).unwrap();
+        assert!(OffsetCache::load(&file, 2, 8, DATA.len()).is_err());
+        remove(&file);
+    }
+
+    #[test]
+    fn reject_bad_gaps() {
+        let file = source("gaps");
+        // the second word begins before the 8 bytes after the first one end.
+        cache(vec![1, 5]).save(&file, 8).unwrap();
+        assert!(OffsetCache::load(&file, 2, 8, DATA.len()).is_err());
+        // the bytes after the last word are beyond the data.
+        cache(vec![1, 16]).save(&file, 8).unwrap();
+        assert!(OffsetCache::load(&file, 2, 8, DATA.len()).is_err());
+        cache(vec![12, 1]).save(&file, 8).unwrap();
+        assert!(OffsetCache::load(&file, 2, 8, DATA.len()).is_err());
+        remove(&file);
+    }
+}
@@ -0,0 +1,199 @@
+use regex::bytes::Regex;
+use std::io::{self, Write};
+
+use super::api;
+use super::reformat::ContentReformat;
+use stardict::{StarDict, WordMergeIter};
+
+/// output format of the command line mode.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    /// plain text, for shell pipelines.
+    Text,
+    /// plain text with ANSI colours, for the terminal.
+    Color,
+    /// the same JSON as the web api.
+    Json,
+}
+
+impl Format {
+    /// parse the `-f` argument: text, color or json.
+    pub fn from_arg(arg: &str) -> Option<Format> {
+        match arg {
+            "text" => Some(Format::Text),
+            "color" | "colour" => Some(Format::Color),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+const BOLD_BLUE: &[u8] = b"\x1b[1;34m";
+const BOLD_GREEN: &[u8] = b"\x1b[1;32m";
+const GRAY: &[u8] = b"\x1b[90m";
+const RESET: &[u8] = b"\x1b[0m";
+
+/// run the command line request `cmd`, like `["lookup", "WORD"]`, and print the result to stdout.
+/// supported commands are `lookup WORD`, `search REGEX`, `glob PATTERN`, `text WORDS`,
+/// `neighbors WORD` and `suggest WORD`. `offset` is used by neighbors, `length` limits the word lists.
+/// `max_dist` is the max edit distance of suggest.
+/// return the process exit code: 0 if something is found, 1 if nothing, 2 for bad usage.
+pub fn run(
+    dict: &StarDict,
+    cr: &ContentReformat,
+    cmd: &[String],
+    format: Format,
+    offset: i32,
+    length: usize,
+    max_dist: usize,
+) -> i32 {
+    if cmd.len() != 2 {
+        eprintln!(
+            "command: lookup WORD | search REGEX | glob PATTERN | text WORDS | neighbors WORD | suggest WORD"
+        );
+        return 2;
+    }
+    let word = cmd[1].as_bytes();
+    let mut out = Vec::new();
+    let found = match (cmd[0].as_str(), format) {
+        ("lookup", Format::Json)
+        | ("search", Format::Json)
+        | ("glob", Format::Json)
+        | ("text", Format::Json)
+        | ("neighbors", Format::Json)
+        | ("suggest", Format::Json) => {
+            let mut req = cmd[0].clone().into_bytes();
+            req.push(b'/');
+            req.extend(word);
+            api::handle(dict, cr, &req, offset, length, max_dist, &mut out);
+            out.push(b'\n');
+            // an empty list or an error object.
+            out.starts_with(b"[{") || out.starts_with(b"[\"")
+        }
+        ("lookup", _) => lookup(dict, word, format, &mut out),
+        ("search", _) | ("glob", _) => match if cmd[0] == "search" {
+            Regex::new(&cmd[1])
+        } else {
+            stardict::glob_regex(&cmd[1])
+        } {
+            Ok(reg) => word_list(dict, dict.search(&reg), length, format, &mut out),
+            Err(e) => {
+                eprintln!("{}", e);
+                return 2;
+            }
+        },
+        ("neighbors", _) => word_list(dict, dict.neighbors(word, offset), length, format, &mut out),
+        ("text", _) => {
+            let found = dict.search_text(word, length);
+            for e in found.iter() {
+                colored(&mut out, format, BOLD_GREEN, e.word);
+                out.push(b' ');
+                colored(&mut out, format, GRAY, e.dictionary.name.as_bytes());
+                out.extend(b"\n    ");
+                out.extend(e.snippet.as_bytes());
+                out.push(b'\n');
+            }
+            !found.is_empty()
+        }
+        ("suggest", _) => {
+            let found = dict.suggest(word, max_dist, length);
+            for w in found.iter() {
+                colored(&mut out, format, BOLD_GREEN, w);
+                out.push(b'\n');
+            }
+            !found.is_empty()
+        }
+        _ => {
+            eprintln!("unknown command: {}", cmd[0]);
+            return 2;
+        }
+    };
+    if io::stdout().write_all(&out).is_err() {
+        return 2;
+    }
+    if found {
+        0
+    } else {
+        1
+    }
+}
+
+/// print like sdcv: the dictionary name, the word, then each text field of the definition.
+fn lookup(dict: &StarDict, word: &[u8], format: Format, out: &mut Vec<u8>) -> bool {
+    let x = match dict.lookup(word) {
+        Ok(x) => x,
+        Err(_) => return false,
+    };
+    if let Some(b) = x.first().and_then(|e| e.base_form.as_ref()) {
+        out.extend(word);
+        out.extend(" → ".as_bytes());
+        out.extend(b);
+        out.extend(b"\n\n");
+    }
+    for e in x.iter() {
+        colored(out, format, BOLD_BLUE, b"-->");
+        colored(out, format, BOLD_BLUE, e.dictionary.name.as_bytes());
+        out.push(b'\n');
+        colored(out, format, BOLD_GREEN, b"-->");
+        if let Some(s) = &e.synonym {
+            // like "colour → see color".
+            colored(out, format, BOLD_GREEN, s);
+            colored(out, format, GRAY, " → see ".as_bytes());
+        }
+        colored(out, format, BOLD_GREEN, &e.word);
+        if e.approximate {
+            colored(out, format, GRAY, b" (approximate)");
+        }
+        out.push(b'\n');
+        if !e.synonyms.is_empty() {
+            colored(out, format, GRAY, b"also: ");
+            colored(out, format, GRAY, &e.synonyms.join(&b", "[..]));
+            out.push(b'\n');
+        }
+        out.push(b'\n');
+        // binary fields are not for the terminal.
+        for (_, b) in e.entry.texts() {
+            out.extend(b);
+            out.push(b'\n');
+        }
+        out.push(b'\n');
+    }
+    !x.is_empty()
+}
+
+/// print one word per line, in colour mode followed by its dictionary.
+fn word_list<'a, T: Iterator<Item = &'a [u8]>>(
+    dict: &StarDict,
+    mut it: WordMergeIter<'a, T>,
+    length: usize,
+    format: Format,
+    out: &mut Vec<u8>,
+) -> bool {
+    let mut n = 0usize;
+    while n < length {
+        let (src, w) = match it.next_with_source() {
+            Some(x) => x,
+            None => break,
+        };
+        colored(out, format, BOLD_GREEN, w);
+        if format == Format::Color {
+            if let Some(ifo) = dict.source_info(src) {
+                out.push(b' ');
+                colored(out, format, GRAY, ifo.name.as_bytes());
+            }
+        }
+        out.push(b'\n');
+        n += 1;
+    }
+    n > 0
+}
+
+fn colored(out: &mut Vec<u8>, format: Format, color: &[u8], text: &[u8]) {
+    if format == Format::Color {
+        out.extend(color);
+        out.extend(text);
+        out.extend(RESET);
+    } else {
+        out.extend(text);
+    }
+}
@@ -2,23 +2,206 @@ use std::fs::File;
 use std::os::unix::prelude::FileExt;
 use std::path;
 
+use flate2::{Decompress, FlushDecompress};
+
 use super::result::DictError;
 
+// gzip header flags.
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
 /// the .dict file reader.
+/// it reads either a plain .dict file or a dictzip compressed .dict.dz file.
 pub struct Dict {
     dictf: File,
+    dz: Option<DictZip>,
+}
+
+/// random access table of a dictzip file, read from the "RA" extra field.
+/// file format:
+/// 1. gzip header, with FEXTRA flag set.
+/// 2. extra subfield 'R' 'A', containing VER, CHLEN, CHCNT and CHCNT chunk sizes, all u16 le.
+/// 3. each chunk is deflated independently and inflates to CHLEN bytes(except the last one).
+struct DictZip {
+    chunk_len: usize,
+    offsets: Vec<u64>, //start of each chunk, plus the end of the last chunk
+}
+
+impl DictZip {
+    /// parse the gzip header and the dictzip RA extra field.
+    fn open(f: &File) -> Result<DictZip, DictError> {
+        let mut head = [0u8; 12];
+        f.read_exact_at(&mut head, 0)?;
+        let flags = head[3];
+        if flags & FEXTRA == 0 {
+            return Err(DictError::My(String::from("not a dictzip file")));
+        }
+        let xlen = u16::from_le_bytes([head[10], head[11]]) as usize;
+        let mut extra = vec![0u8; xlen];
+        f.read_exact_at(&mut extra, 12)?;
+
+        let mut ra = None;
+        let mut i = 0usize;
+        while i + 4 <= xlen {
+            let sublen = u16::from_le_bytes([extra[i + 2], extra[i + 3]]) as usize;
+            if extra[i] == b'R' && extra[i + 1] == b'A' && i + 4 + sublen <= xlen {
+                ra = Some(&extra[i + 4..i + 4 + sublen]);
+                break;
+            }
+            i += 4 + sublen;
+        }
+        let ra = match ra {
+            Some(r) if r.len() >= 6 => r,
+            _ => return Err(DictError::My(String::from("no dictzip RA field"))),
+        };
+        let chunk_len = u16::from_le_bytes([ra[2], ra[3]]) as usize;
+        let chunk_count = u16::from_le_bytes([ra[4], ra[5]]) as usize;
+        // `read()` divides by the chunk length, a malformed header must not get there.
+        if chunk_len == 0 || chunk_count == 0 || ra.len() < 6 + chunk_count * 2 {
+            return Err(DictError::My(String::from("bad dictzip chunk table")));
+        }
+
+        // skip FNAME, FCOMMENT and FHCRC to find where the data begins.
+        let mut pos = 12 + xlen as u64;
+        for flag in [FNAME, FCOMMENT].iter() {
+            if flags & flag != 0 {
+                let mut c = [0u8; 1];
+                loop {
+                    f.read_exact_at(&mut c, pos)?;
+                    pos += 1;
+                    if c[0] == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+        if flags & FHCRC != 0 {
+            pos += 2;
+        }
+
+        let mut offsets = Vec::with_capacity(chunk_count + 1);
+        offsets.push(pos);
+        for c in ra[6..6 + chunk_count * 2].chunks(2) {
+            pos += u16::from_le_bytes([c[0], c[1]]) as u64;
+            offsets.push(pos);
+        }
+        Ok(DictZip { chunk_len, offsets })
+    }
+    /// inflate the chunk `i`, append it to `out`.
+    fn inflate(&self, f: &File, i: usize, out: &mut Vec<u8>) -> Result<(), DictError> {
+        let start = self.offsets[i];
+        let mut input = vec![0u8; (self.offsets[i + 1] - start) as usize];
+        f.read_exact_at(&mut input, start)?;
+
+        let mut dec = Decompress::new(false);
+        let begin = out.len();
+        out.reserve(self.chunk_len);
+        dec.decompress_vec(&input, out, FlushDecompress::Sync)
+            .map_err(|e| DictError::My(format!("inflate chunk {} failed: {}", i, e)))?;
+        if out.len() - begin > self.chunk_len {
+            return Err(DictError::My(format!("bad dictzip chunk {}", i)));
+        }
+        Ok(())
+    }
+    /// read `length` from `start` of the uncompressed data.
+    fn read(&self, f: &File, start: u64, length: usize) -> Result<Vec<u8>, DictError> {
+        if length == 0 {
+            return Ok(Vec::new());
+        }
+        let first = (start / self.chunk_len as u64) as usize;
+        let last = ((start + length as u64 - 1) / self.chunk_len as u64) as usize;
+        if last + 1 >= self.offsets.len() {
+            return Err(DictError::NotFound(last));
+        }
+        let mut buf = Vec::with_capacity((last - first + 1) * self.chunk_len);
+        for i in first..=last {
+            self.inflate(f, i, &mut buf)?;
+        }
+        let skip = (start - first as u64 * self.chunk_len as u64) as usize;
+        if skip + length > buf.len() {
+            return Err(DictError::NotFound(last));
+        }
+        buf.truncate(skip + length);
+        buf.drain(..skip);
+        Ok(buf)
+    }
 }
 
 impl Dict {
-    /// test and open the .dict file.
+    /// test and open the .dict file. a gzip(dictzip) file is detected by its magic number.
     pub fn open(file: &path::Path) -> Result<Dict, DictError> {
         let f = File::open(file)?;
-        Ok(Dict { dictf: f })
+        let mut magic = [0u8; 3];
+        let dz = if f.read_exact_at(&mut magic, 0).is_ok() && magic == [0x1f, 0x8b, 8] {
+            Some(DictZip::open(&f)?)
+        } else {
+            None
+        };
+        Ok(Dict { dictf: f, dz })
     }
     /// read `length` from `start`
     pub fn read(&self, start: u64, length: usize) -> Result<Vec<u8>, DictError> {
+        if let Some(dz) = &self.dz {
+            return dz.read(&self.dictf, start, length);
+        }
         let mut result = vec![0u8; length];
         self.dictf.read_exact_at(&mut result, start)?;
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{Compress, Compression, FlushCompress};
+
+    // write `data` as a dictzip file of `chunk_len` chunks, with a file name in the header.
+    fn write_dictzip(file: &path::Path, data: &[u8], chunk_len: usize) {
+        let mut chunks = Vec::new();
+        for c in data.chunks(chunk_len) {
+            let mut out = Vec::with_capacity(c.len() + 64);
+            Compress::new(Compression::default(), false)
+                .compress_vec(c, &mut out, FlushCompress::Finish)
+                .unwrap();
+            chunks.push(out);
+        }
+        let mut ra = vec![b'R', b'A', 0, 0, 1, 0];
+        ra.extend((chunk_len as u16).to_le_bytes());
+        ra.extend((chunks.len() as u16).to_le_bytes());
+        for c in chunks.iter() {
+            ra.extend((c.len() as u16).to_le_bytes());
+        }
+        let sublen = (ra.len() - 4) as u16;
+        ra[2..4].copy_from_slice(&sublen.to_le_bytes());
+
+        let mut out = vec![0x1f, 0x8b, 8, FEXTRA | FNAME, 0, 0, 0, 0, 0, 3];
+        out.extend((ra.len() as u16).to_le_bytes());
+        out.extend(ra);
+        out.extend(b"test.dict\0");
+        for c in chunks {
+            out.extend(c);
+        }
+        std::fs::write(file, out).unwrap();
+    }
+
+    #[test]
+    fn dictzip_read_across_chunks() {
+        let data: Vec<u8> = (0..100u8).collect();
+        let file =
+            std::env::temp_dir().join(format!("stardict-test-{}.dict.dz", std::process::id()));
+        write_dictzip(&file, &data, 8);
+        let dict = Dict::open(&file).unwrap();
+        assert!(dict.dz.is_some());
+        for (start, length) in [(0, 8), (5, 6), (6, 20), (8, 8), (90, 10), (99, 1), (0, 100)] {
+            assert_eq!(
+                dict.read(start as u64, length).unwrap(),
+                &data[start..start + length]
+            );
+        }
+        assert!(dict.read(95, 10).is_err());
+        assert!(dict.read(100, 1).is_err());
+        std::fs::remove_file(&file).unwrap();
+    }
+}
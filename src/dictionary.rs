@@ -1,37 +1,95 @@
 //extern crate regex;
 
-use std::cmp::Ordering;
+use std::os::unix::fs::MetadataExt;
+use std::sync::OnceLock;
 use std::{borrow::Cow, fs, path, str};
 
-use regex::bytes::Regex;
-use regex::Error;
+use super::cache;
 use super::dict::Dict;
+use super::entry::Entry;
+use super::fulltext::{self, TextIndex};
+use super::fuzzy::{SuggestIndex, Suggester};
 use super::idx::Idx;
 use super::ifo::Ifo;
+use super::normalize::NormTable;
+use super::resource::Resource;
 use super::result::DictError;
 use super::syn::Syn;
+use regex::bytes::Regex;
+use regex::Error;
 
 /// used to make Syn and Idx iterator work together.
+#[derive(Clone, Copy)]
 pub enum IdxRef<'a> {
     Ref(&'a Idx),
     SynRef(&'a Option<Syn>),
 }
-/// a Dictionary contains Ifo, Idx, Dict and Syn(optionally).
+/// a Dictionary contains Ifo, Idx, Dict, and Syn and Resource(optionally).
 pub struct Dictionary {
     pub ifo: Ifo,
-    pub idx: Idx,
-    pub syn: Option<Syn>,
-    pub dict: Dict,
+    pub(crate) idx: Idx,
+    pub(crate) syn: Option<Syn>,
+    pub(crate) dict: Dict,
+    pub(crate) res: Option<Resource>,
+    ifo_file: path::PathBuf,
+    dict_file: path::PathBuf,
+    text: OnceLock<TextIndex>, //built by prepare(), or on the first full-text search
+    norm: OnceLock<NormTable>, //built by prepare(), or on the first approximate lookup
+    fuzzy: OnceLock<SuggestIndex>, //the words of Idx then Syn, built like the others
+}
+/// the name, size, modified time(secs and nanos) and inode of a dictionary file.
+/// a file replaced by rename has a new inode, even with the same size and time.
+pub type FileStamp = (std::ffi::OsString, u64, u64, u32, u64);
+/// how the looked up word matched an entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchKind {
+    /// the headword in Idx is the word.
+    Headword,
+    /// a synonym in Syn is the word.
+    Synonym,
+    /// the headword or the synonym only equals the word with case folding, or the
+    /// normalized key.
+    CaseFolded,
+}
+impl MatchKind {
+    /// the name of the kind, like "case_folded".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchKind::Headword => "headword",
+            MatchKind::Synonym => "synonym",
+            MatchKind::CaseFolded => "case_folded",
+        }
+    }
 }
 /// the successful result a lookup would return.
 pub struct LookupResult<'a> {
     pub dictionary: &'a Ifo,
     pub word: Vec<u8>,
-    pub result: Vec<u8>,
+    /// the position of `word` in Idx, see `Dictionary::entry()`.
+    pub index: usize,
+    /// the definition, parsed by the sametypesequence of the dictionary.
+    pub entry: Entry,
+    pub kind: MatchKind,
+    /// the synonym in Syn which matched, `word` is the headword it points to.
+    pub synonym: Option<Vec<u8>>,
+    /// all synonyms in Syn pointing to `word`, its alternate forms, except `synonym`.
+    pub synonyms: Vec<Vec<u8>>,
+    /// found by the normalized key(see `normalize::key`), not the word itself.
+    pub approximate: bool,
+    /// the base form looked up instead, when the inflected word is not found.
+    /// see `lemma::base_forms`.
+    pub base_form: Option<Vec<u8>>,
+}
+/// an entry found by full-text search, with a short text around the hit.
+pub struct TextHit<'a> {
+    pub dictionary: &'a Ifo,
+    pub word: &'a [u8],
+    pub snippet: String,
 }
 /// a regular expression search iterator.
 pub struct IdxIter<'a> {
     cur: usize,
+    end: usize, //the words in [cur, end) may match
     idx: IdxRef<'a>,
     matcher: Cow<'a, Regex>,
 }
@@ -41,44 +99,62 @@ pub struct DictNeighborIter<'a> {
     idx: IdxRef<'a>,
 }
 impl Dictionary {
-    /// create a Dictionary from the dictionary directory.
-    pub fn new(root: &path::Path, base: &path::Path) -> Result<Dictionary, DictError> {
-        for it in fs::read_dir(root)? {
-            let it = it?.path();
-            if it.is_file() {
-                if let Some(ext) = it.extension() {
-                    if let Some("ifo") = ext.to_str() {
-                        let ifo = Ifo::open(&it, base)?;
-                        let mut file = it.to_path_buf();
-                        file.set_extension("idx");
-                        let idx = Idx::open(
-                            &file,
-                            ifo.idx_file_size,
-                            ifo.word_count,
-                            (ifo.idxoffsetbits / 8 + 4) as u8,
-                        )?;
-                        file.set_extension("dict");
-                        let dict = Dict::open(&file)?;
-                        file.set_extension("syn");
-                        let syn = Syn::open(&file, ifo.syn_word_count).ok();
-                        return Ok(Dictionary {
-                            ifo,
-                            idx,
-                            dict,
-                            syn,
-                        });
-                    }
-                }
-            }
-        }
-        Err(DictError::My(format!(
-            "bad dictionary directory {}",
-            root.display()
-        )))
+    /// create a Dictionary from its .ifo file, the other files are next to it with the same
+    /// name. `base` is the root directory of all dictionaries, see `Ifo::dict_path`.
+    /// the .idx, .syn and res.ridx are memory mapped if `mapped`, see `IdxData::open()`.
+    pub fn new(
+        ifo_file: &path::Path,
+        base: &path::Path,
+        mapped: bool,
+    ) -> Result<Dictionary, DictError> {
+        let ifo = Ifo::open(ifo_file, base)?;
+        let mut file = ifo_file.to_path_buf();
+        file.set_extension("idx");
+        let idx = Idx::open(
+            &file,
+            ifo.idx_file_size,
+            ifo.word_count,
+            (ifo.idxoffsetbits / 8 + 4) as u8,
+            mapped,
+        )?;
+        file.set_extension("dict");
+        let dict_file = if file.is_file() {
+            file.clone()
+        } else {
+            file.with_extension("dict.dz")
+        };
+        let dict = Dict::open(&dict_file)?;
+        file.set_extension("syn");
+        let syn = if ifo.syn_word_count > 0 {
+            Syn::open(&file, ifo.syn_word_count, mapped).ok()
+        } else {
+            None
+        };
+        // the resource database is shared by the dictionaries in the same directory.
+        let dir = ifo_file.parent().unwrap_or(path::Path::new(""));
+        let res = if dir.join("res.rifo").is_file() {
+            Resource::open(dir, mapped)
+                .map_err(|e| eprintln!("ignore resource of {}: {}", dir.display(), e))
+                .ok()
+        } else {
+            None
+        };
+        Ok(Dictionary {
+            ifo,
+            idx,
+            dict,
+            syn,
+            res,
+            ifo_file: ifo_file.to_path_buf(),
+            dict_file,
+            text: OnceLock::new(),
+            norm: OnceLock::new(),
+            fuzzy: OnceLock::new(),
+        })
     }
     /// get the following neighbor words from Idx after `word` from `off`.
     /// if `off` is negative, list from before `-off`.
-    pub fn neighbors(&self, word: &[u8], off: i32) -> DictNeighborIter {
+    pub fn neighbors(&self, word: &[u8], off: i32) -> DictNeighborIter<'_> {
         let ret = match self.idx.get(word) {
             Ok(i) => i,
             Err(i) => i,
@@ -93,8 +169,8 @@ impl Dictionary {
     }
     /// get the following neighbor words from Syn after `word` from `off`.
     /// if `off` is negative, list from before `-off`.
-    pub fn neighbors_syn(&self, word: &[u8], off: i32) -> DictNeighborIter {
-        let mut start: usize = usize::max_value();
+    pub fn neighbors_syn(&self, word: &[u8], off: i32) -> DictNeighborIter<'_> {
+        let mut start: usize = usize::MAX;
         if let Some(s) = &self.syn {
             let ret = match s.get(word) {
                 Ok(i) => i,
@@ -110,95 +186,318 @@ impl Dictionary {
     }
 
     /// search Idx by regular expression
-    pub fn search(&self, expr: &[u8]) -> Result<IdxIter, Error> {
+    pub fn search(&self, expr: &[u8]) -> Result<IdxIter<'_>, Error> {
         match str::from_utf8(expr) {
             Ok(e) => {
                 let reg = Regex::new(e)?;
-                Ok(IdxIter {
-                    cur: 0,
-                    idx: IdxRef::Ref(&self.idx),
-                    matcher: Cow::Owned(reg),
-                })
+                Ok(IdxIter::new(IdxRef::Ref(&self.idx), Cow::Owned(reg)))
             }
             _ => Err(Error::Syntax(String::from("bad utf8"))),
         }
     }
     /// search Syn by pre-created regular expression object.
-    pub fn search_syn<'a>(&'a self, reg: &'a Regex) -> IdxIter {
-        IdxIter {
-            cur: 0,
-            idx: IdxRef::SynRef(&self.syn),
-            matcher: Cow::Borrowed(reg),
-        }
+    pub fn search_syn<'a>(&'a self, reg: &'a Regex) -> IdxIter<'a> {
+        IdxIter::new(IdxRef::SynRef(&self.syn), Cow::Borrowed(reg))
     }
     /// search Idx by pre-created regular expression object.
-    pub fn search_regex<'a>(&'a self, reg: &'a Regex) -> IdxIter {
-        IdxIter {
-            cur: 0,
-            idx: IdxRef::Ref(&self.idx),
-            matcher: Cow::Borrowed(reg),
+    pub fn search_regex<'a>(&'a self, reg: &'a Regex) -> IdxIter<'a> {
+        IdxIter::new(IdxRef::Ref(&self.idx), Cow::Borrowed(reg))
+    }
+    /// collect the words of Idx and Syn within the edit distance of `sug`, with their distance.
+    /// only the candidates of the `SuggestIndex` are checked, at most `cap` of them.
+    pub fn suggest<'a>(
+        &'a self,
+        sug: &mut Suggester,
+        cap: usize,
+        out: &mut Vec<(usize, &'a [u8])>,
+    ) {
+        for i in self.suggest_index().candidates(sug, cap) {
+            let i = i as usize;
+            let w = if i < self.idx.len() {
+                self.idx.get_word(i)
+            } else {
+                match &self.syn {
+                    Some(s) => s.get_word(i - self.idx.len()),
+                    None => continue,
+                }
+            };
+            if let Ok(w) = w {
+                if let Some(d) = sug.distance(w) {
+                    out.push((d, w));
+                }
+            }
         }
     }
-    /// lookup `word` in Dictionary. find from Idx, and also find all matches from Syn.
-    pub fn lookup(&self, word: &[u8]) -> Result<Vec<LookupResult>, DictError> {
-        let mut possible = Vec::with_capacity(4);
-        possible.push(self.idx.get(word));
-
+    /// find the entries whose definition contains all `tokens`(see `fulltext::tokenize`),
+    /// until `out` has `limit` hits. the full-text index is loaded or built on the first use,
+    /// unless `prepare()` did it before.
+    pub fn search_text<'a>(&'a self, tokens: &[String], limit: usize, out: &mut Vec<TextHit<'a>>) {
+        let sts = self.ifo.same_type_sequence.as_bytes();
+        for i in self.text_index().find(tokens) {
+            if out.len() >= limit {
+                break;
+            }
+            let i = i as usize;
+            let word = match self.idx.get_word(i) {
+                Ok(w) => w,
+                Err(_) => continue,
+            };
+            let data = match self.idx.get_offset_length(i) {
+                Ok((off, len)) => match self.dict.read(off, len as usize) {
+                    Ok(d) => d,
+                    Err(_) => continue,
+                },
+                Err(_) => continue,
+            };
+            if let Some(snippet) = fulltext::snippet(sts, &data, tokens) {
+                out.push(TextHit {
+                    dictionary: &self.ifo,
+                    word,
+                    snippet,
+                });
+            }
+        }
+    }
+    /// build the full-text index, the normalized keys, the suggestion candidates and the
+    /// synonyms of each word now, instead of in the first request which needs each.
+    pub fn prepare(&self) {
+        self.text_index();
+        self.norm_table();
+        self.suggest_index();
         if let Some(s) = &self.syn {
-            if let Ok(i) = s.get(word) {
-                possible.push(s.get_offset(i));
-                //check neighbors
-                let mut c = i - 1;
-                //left neighbors
-                while let Ok(w) = s.get_word(c) {
-                    if Idx::dict_cmp(word, &w, true) == Ordering::Equal {
-                        possible.push(s.get_offset(c));
-                    } else {
-                        break;
-                    }
-                    c -= 1;
-                }
-                //right neighbors
-                c = i + 1;
-                while let Ok(w) = s.get_word(c) {
-                    if Idx::dict_cmp(word, &w, true) == Ordering::Equal {
-                        possible.push(s.get_offset(c));
+            s.prepare();
+        }
+    }
+    fn text_index(&self) -> &TextIndex {
+        self.text.get_or_init(|| {
+            let sts = self.ifo.same_type_sequence.as_bytes();
+            TextIndex::open(&self.dict_file, &self.idx, &self.dict, sts).unwrap_or_else(|e| {
+                eprintln!(
+                    "full-text index failed for {}: {}",
+                    self.dict_file.display(),
+                    e
+                );
+                TextIndex::default()
+            })
+        })
+    }
+    fn norm_table(&self) -> &NormTable {
+        self.norm
+            .get_or_init(|| NormTable::new(&self.idx, self.syn.as_ref()))
+    }
+    fn suggest_index(&self) -> &SuggestIndex {
+        self.fuzzy.get_or_init(|| {
+            let syn = self
+                .syn
+                .iter()
+                .flat_map(|s| (0..s.len()).map(move |i| s.get_word(i)));
+            let words = (0..self.idx.len()).map(|i| self.idx.get_word(i)).chain(syn);
+            SuggestIndex::new(words.map(|w| w.unwrap_or(b"")))
+        })
+    }
+    /// the .ifo file of the dictionary.
+    pub fn ifo_file(&self) -> &path::Path {
+        &self.ifo_file
+    }
+    /// the stamps of the files of the dictionary of `ifo_file`, and the resource database
+    /// next to it, sorted by name. the caches written next to them(.oft, .fts) are not
+    /// included.
+    pub fn stamp(ifo_file: &path::Path) -> Vec<FileStamp> {
+        let mut ret = Vec::new();
+        let (dir, stem) = match (ifo_file.parent(), ifo_file.file_stem()) {
+            (Some(d), Some(s)) => (d, s.to_string_lossy()),
+            _ => return ret,
+        };
+        let files = match fs::read_dir(dir) {
+            Ok(f) => f,
+            Err(_) => return ret,
+        };
+        for it in files.flatten() {
+            let name = it.file_name();
+            let name = name.to_string_lossy();
+            let mine = match name.strip_prefix(stem.as_ref()) {
+                Some(ext) => matches!(
+                    ext,
+                    ".ifo" | ".idx" | ".idx.gz" | ".syn" | ".syn.gz" | ".dict" | ".dict.dz"
+                ),
+                None => false,
+            };
+            let res = matches!(
+                name.as_ref(),
+                "res.rifo" | "res.ridx" | "res.ridx.gz" | "res.rdic" | "res.rdic.dz"
+            );
+            if !mine && !res {
+                continue;
+            }
+            let path = it.path();
+            if let (Ok((size, secs, nanos)), Ok(meta)) = (cache::stamp(&path), fs::metadata(&path))
+            {
+                ret.push((it.file_name(), size, secs, nanos, meta.ino()));
+            }
+        }
+        ret.sort();
+        ret
+    }
+    /// the definition of the word at `index` of Idx.
+    pub fn entry(&self, index: usize) -> Result<Entry, DictError> {
+        let (eoffset, elength) = self.idx.get_offset_length(index)?;
+        Ok(Entry::parse(
+            self.ifo.same_type_sequence.as_bytes(),
+            &self.dict.read(eoffset, elength as usize)?,
+        ))
+    }
+    /// lookup `word` in Dictionary. find all case variants from Idx and Syn, the exact
+    /// matches first. if nothing is found, find by the normalized key, and the results
+    /// are approximate.
+    pub fn lookup(&self, word: &[u8]) -> Result<Vec<LookupResult<'_>>, DictError> {
+        // the Idx positions, how they match, and the synonyms.
+        let mut possible: Vec<(usize, MatchKind, Option<&[u8]>)> = Vec::with_capacity(4);
+        // "Polish" and "polish", "US" and "us" are all found.
+        for i in self.idx.get_all(word) {
+            let kind = if self.idx.get_word(i)? == word {
+                MatchKind::Headword
+            } else {
+                MatchKind::CaseFolded
+            };
+            possible.push((i, kind, None));
+        }
+        if let Some(s) = &self.syn {
+            // the matches are not always neighbors with Unicode case folding.
+            for i in s.get_all(word) {
+                if let Ok(p) = s.get_offset(i) {
+                    let w = s.get_word(i)?;
+                    let kind = if w == word {
+                        MatchKind::Synonym
                     } else {
-                        break;
-                    }
-                    c += 1;
+                        MatchKind::CaseFolded
+                    };
+                    possible.push((p, kind, Some(w)));
                 }
             }
         }
+        let approximate = possible.is_empty();
+        if approximate {
+            possible.extend(
+                self.norm_table()
+                    .get(word)
+                    .into_iter()
+                    .map(|i| (i, MatchKind::CaseFolded, None)),
+            );
+        }
+        // stable, the Idx matches stay before the Syn matches.
+        possible.sort_by_key(|(_, kind, _)| *kind == MatchKind::CaseFolded);
 
         let mut ret = Vec::new();
-        for v in possible.iter() {
-            if let Ok(i) = v {
-                let (eoffset, elength) = self.idx.get_offset_length(*i)?;
-                ret.push(LookupResult {
-                    dictionary: &self.ifo,
-                    word: self.idx.get_word(*i)?,
-                    result: self.dict.read(eoffset as u64, elength as usize)?,
-                });
+        for (n, (i, kind, synonym)) in possible.iter().enumerate() {
+            // Idx and Syn may find the same entry.
+            if possible[..n].iter().any(|(p, _, _)| p == i) {
+                continue;
             }
+            ret.push(LookupResult {
+                dictionary: &self.ifo,
+                word: self.idx.get_word(*i)?.to_vec(),
+                index: *i,
+                entry: self.entry(*i)?,
+                kind: *kind,
+                synonym: synonym.map(|w| w.to_vec()),
+                synonyms: self.syn.as_ref().map_or_else(Vec::new, |s| {
+                    s.synonyms_of(*i)
+                        .into_iter()
+                        .filter(|w| Some(*w) != *synonym)
+                        .map(|w| w.to_vec())
+                        .collect()
+                }),
+                approximate,
+                base_form: None,
+            });
         }
-        if ret.len() > 0 {
+        if !ret.is_empty() {
             Ok(ret)
         } else {
             Err(DictError::NotFound(0))
         }
     }
 }
+/// translate a glob pattern to an anchored regular expression, like desktop StarDict:
+/// `*` matches any chars, `?` matches one char, the others match themselves.
+/// the literal chars before the first wildcard narrow the search like `^inter`.
+pub fn glob_regex(pattern: &str) -> Result<Regex, Error> {
+    let mut expr = String::with_capacity(pattern.len() + 8);
+    expr.push('^');
+    let mut lit = String::new();
+    for c in pattern.chars() {
+        if c == '*' || c == '?' {
+            expr.push_str(&regex::escape(&lit));
+            lit.clear();
+            expr.push_str(if c == '*' { ".*" } else { "." });
+        } else {
+            lit.push(c);
+        }
+    }
+    expr.push_str(&regex::escape(&lit));
+    expr.push('$');
+    Regex::new(&expr)
+}
+/// the literal beginning of an anchored regular expression, like `inter` of `^inter.*$`.
+/// it is conservative: empty if the expression is not simple enough to tell.
+fn literal_prefix(expr: &str) -> Vec<u8> {
+    let mut prefix = String::new();
+    if !expr.starts_with('^') || expr.contains('|') {
+        return Vec::new();
+    }
+    let mut it = expr[1..].chars();
+    let mut last = 0usize; //the prefix length before the last char
+    while let Some(c) = it.next() {
+        let c = match c {
+            '\\' => match it.next() {
+                Some(e) if e.is_ascii_punctuation() => e,
+                _ => break,
+            },
+            // the last char may be absent.
+            '?' | '*' | '{' => {
+                prefix.truncate(last);
+                break;
+            }
+            '.' | '+' | '[' | ']' | '(' | ')' | '}' | '^' | '$' | '#' => break,
+            c if c.is_whitespace() => break,
+            c => c,
+        };
+        last = prefix.len();
+        prefix.push(c);
+    }
+    prefix.into_bytes()
+}
+impl<'a> IdxIter<'a> {
+    /// when `matcher` has a literal prefix, only the words beginning with it are matched.
+    fn new(idx: IdxRef<'a>, matcher: Cow<'a, Regex>) -> IdxIter<'a> {
+        let prefix = literal_prefix(matcher.as_str());
+        let range = if prefix.is_empty() {
+            0..usize::MAX
+        } else {
+            match idx {
+                IdxRef::Ref(r) => r.prefix_range(&prefix),
+                IdxRef::SynRef(Some(s)) => s.prefix_range(&prefix),
+                IdxRef::SynRef(None) => 0..0,
+            }
+        };
+        IdxIter {
+            cur: range.start,
+            end: range.end,
+            idx,
+            matcher,
+        }
+    }
+}
 impl<'a> Iterator for IdxIter<'a> {
-    type Item = Vec<u8>;
+    type Item = &'a [u8];
     fn next(&mut self) -> Option<Self::Item> {
-        match &self.idx {
+        match self.idx {
             IdxRef::Ref(r) => {
-                while self.cur < r.len() {
+                while self.cur < r.len().min(self.end) {
                     let v = r.get_word(self.cur);
                     self.cur += 1;
                     if let Ok(e) = v {
-                        if self.matcher.is_match(&e) {
+                        if self.matcher.is_match(e) {
                             return Some(e);
                         }
                     }
@@ -209,11 +508,11 @@ impl<'a> Iterator for IdxIter<'a> {
                     return None;
                 }
                 let s = r.as_ref().unwrap();
-                while self.cur < s.len() {
+                while self.cur < s.len().min(self.end) {
                     let v = s.get_word(self.cur);
                     self.cur += 1;
                     if let Ok(e) = v {
-                        if self.matcher.is_match(&e) {
+                        if self.matcher.is_match(e) {
                             return Some(e);
                         }
                     }
@@ -224,9 +523,9 @@ impl<'a> Iterator for IdxIter<'a> {
     }
 }
 impl<'a> Iterator for DictNeighborIter<'a> {
-    type Item = Vec<u8>;
+    type Item = &'a [u8];
     fn next(&mut self) -> Option<Self::Item> {
-        match &self.idx {
+        match self.idx {
             IdxRef::Ref(r) => {
                 if self.cur < r.len() {
                     let v = r.get_word(self.cur);
@@ -251,3 +550,40 @@ impl<'a> Iterator for DictNeighborIter<'a> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_prefix_of_patterns() {
+        assert_eq!(literal_prefix("^inter.*$"), b"inter");
+        assert_eq!(literal_prefix("^a\\.b\\*c"), b"a.b*c");
+        assert_eq!(literal_prefix("^a\\d"), b"a");
+        assert_eq!(literal_prefix("^abc?d"), b"ab");
+        assert_eq!(literal_prefix("^abc*"), b"ab");
+        assert_eq!(literal_prefix("^ab{2}"), b"a");
+        assert_eq!(literal_prefix("^ab+c"), b"ab");
+        assert_eq!(literal_prefix("^a?"), b"");
+        assert_eq!(literal_prefix("^ab|^cd"), b"");
+        assert_eq!(literal_prefix("^(ab|cd)"), b"");
+        assert_eq!(literal_prefix("abc"), b"");
+    }
+
+    #[test]
+    fn glob_regex_escapes() {
+        let r = glob_regex("a.b*").unwrap();
+        assert!(r.is_match(b"a.b") && r.is_match(b"a.bcd"));
+        assert!(!r.is_match(b"axb") && !r.is_match(b"xa.b"));
+        let r = glob_regex("c++?").unwrap();
+        assert!(r.is_match(b"c++x"));
+        assert!(!r.is_match(b"c++") && !r.is_match(b"cc"));
+        let r = glob_regex("(x)[y]{2}$^|\\").unwrap();
+        assert!(r.is_match(b"(x)[y]{2}$^|\\"));
+        assert!(!r.is_match(b"x"));
+        assert_eq!(
+            literal_prefix(glob_regex("a.b*c").unwrap().as_str()),
+            b"a.b"
+        );
+    }
+}
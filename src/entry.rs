@@ -0,0 +1,130 @@
+/// the definition of a word, parsed into its fields by the StarDict format.
+pub struct Entry {
+    /// (type, content) of each field. lower case types are text, like 'm' for plain
+    /// text and 'h' for html, upper case types are binary, like 'W' for wav and 'P'
+    /// for a picture.
+    pub fields: Vec<(u8, Vec<u8>)>,
+}
+
+impl Entry {
+    /// parse the definition `data` of a dictionary with sametypesequence `sts`,
+    /// which is empty if each field begins with its own type.
+    pub fn parse(sts: &[u8], data: &[u8]) -> Entry {
+        Entry {
+            fields: fields(sts, data)
+                .into_iter()
+                .map(|(t, f)| (t, f.to_vec()))
+                .collect(),
+        }
+    }
+    /// the text fields, skipping the binary ones.
+    pub fn texts(&self) -> impl Iterator<Item = (u8, &[u8])> {
+        self.fields
+            .iter()
+            .filter(|(t, _)| !Entry::is_binary(*t))
+            .map(|(t, f)| (*t, f.as_slice()))
+    }
+    /// upper case types are binary data, the others are text.
+    pub fn is_binary(t: u8) -> bool {
+        t.is_ascii_uppercase()
+    }
+    /// the MIME type of the binary field `data` of type `t`: 'W' is wav, and the
+    /// picture of 'P' is told by its magic bytes.
+    pub fn mime_type(t: u8, data: &[u8]) -> &'static str {
+        match t {
+            b'W' => "audio/wav",
+            b'P' if data.starts_with(b"\x89PNG") => "image/png",
+            b'P' if data.starts_with(b"\xff\xd8") => "image/jpeg",
+            b'P' if data.starts_with(b"GIF8") => "image/gif",
+            b'P' if data.starts_with(b"BM") => "image/bmp",
+            b'P' if data.len() > 12 && &data[8..12] == b"WEBP" => "image/webp",
+            _ => "application/octet-stream",
+        }
+    }
+}
+
+/// split the definition `data` into its fields, as (type, content) pairs.
+/// with sametypesequence `sts`, the types are not stored in `data`, and the last
+/// field takes the rest of `data`. lower case fields end with '\0', upper case
+/// fields begin with their size in u32 be.
+pub(crate) fn fields<'a>(sts: &[u8], mut data: &'a [u8]) -> Vec<(u8, &'a [u8])> {
+    let mut ret = Vec::new();
+    let mut types = sts.iter();
+    loop {
+        let t = if sts.is_empty() {
+            match data.split_first() {
+                Some((t, rest)) => {
+                    data = rest;
+                    *t
+                }
+                None => break,
+            }
+        } else {
+            match types.next() {
+                Some(t) => *t,
+                None => break,
+            }
+        };
+        let field;
+        if !sts.is_empty() && types.len() == 0 {
+            field = data;
+            data = &[];
+        } else if t.is_ascii_lowercase() {
+            let n = data.iter().position(|c| *c == 0).unwrap_or(data.len());
+            field = &data[..n];
+            data = &data[(n + 1).min(data.len())..];
+        } else {
+            if data.len() < 4 {
+                break;
+            }
+            let n = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+            let n = n.min(data.len() - 4);
+            field = &data[4..4 + n];
+            data = &data[4 + n..];
+        }
+        ret.push((t, field));
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_with_sametypesequence() {
+        let e = Entry::parse(b"m", b"a whole\0text");
+        assert_eq!(e.fields, vec![(b'm', b"a whole\0text".to_vec())]);
+
+        let data = b"first\0\0\0\0\x03\0\x01\0rest";
+        let e = Entry::parse(b"mWh", data);
+        assert_eq!(
+            e.fields,
+            vec![
+                (b'm', b"first".to_vec()),
+                (b'W', b"\0\x01\0".to_vec()),
+                (b'h', b"rest".to_vec()),
+            ]
+        );
+        let texts: Vec<_> = e.texts().map(|(t, _)| t).collect();
+        assert_eq!(texts, vec![b'm', b'h']);
+    }
+
+    #[test]
+    fn parse_without_sametypesequence() {
+        let data = b"mone\0P\0\0\0\x04\0\0\0\x01hlast";
+        let e = Entry::parse(b"", data);
+        assert_eq!(
+            e.fields,
+            vec![
+                (b'm', b"one".to_vec()),
+                (b'P', b"\0\0\0\x01".to_vec()),
+                (b'h', b"last".to_vec()),
+            ]
+        );
+        // the size of an upper case field is limited to the data left.
+        let e = Entry::parse(b"", b"W\0\0\0\x09ab");
+        assert_eq!(e.fields, vec![(b'W', b"ab".to_vec())]);
+        assert!(Entry::parse(b"", b"").fields.is_empty());
+    }
+}
@@ -0,0 +1,383 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::ops::Range;
+use std::path;
+
+use super::cache::{read_u32, read_u64, read_word, stamp};
+use super::dict::Dict;
+use super::entry::fields;
+use super::idx::Idx;
+use super::result::DictError;
+
+const MAGIC: &[u8; 8] = b"SDFTS001";
+// the .dict file is read in windows of this size while building the index.
+const WINDOW: usize = 4 << 20;
+// chars kept on each side of a hit in a snippet.
+const SNIPPET_CHARS: usize = 40;
+
+/// the full-text index of the definitions of a dictionary. each token maps to the
+/// Idx positions of the entries containing it. it is persisted to a sidecar `.fts`
+/// file next to the .dict file, and rebuilt when the .dict file changes.
+/// file format(all integers are u32/u64 le):
+/// 1. magic "SDFTS001".
+/// 2. .dict file size u64, mtime seconds u64, mtime nanoseconds u32.
+/// 3. entry count u32, token count u32.
+/// 4. each token as length u32 and bytes, then the position count u32 and the positions u32.
+#[derive(Default)]
+pub struct TextIndex {
+    tokens: Vec<u8>,        //all tokens, sorted
+    token_ends: Vec<u32>,   //end of each token
+    postings: Vec<u32>,     //Idx positions of all tokens, sorted for each token
+    posting_ends: Vec<u32>, //end of the positions of each token
+}
+
+/// `XYZ.dict` is indexed in `XYZ.dict.fts`.
+fn cache_path(file: &path::Path) -> path::PathBuf {
+    let mut p = file.as_os_str().to_owned();
+    p.push(".fts");
+    path::PathBuf::from(p)
+}
+
+impl TextIndex {
+    /// load the index of the .dict `file` from its cache, or build it from all entries of
+    /// `idx` and save the cache. `sts` is the sametypesequence of the dictionary.
+    pub fn open(
+        file: &path::Path,
+        idx: &Idx,
+        dict: &Dict,
+        sts: &[u8],
+    ) -> Result<TextIndex, DictError> {
+        if let Ok(t) = TextIndex::load(file, idx.len()) {
+            return Ok(t);
+        }
+        let t = TextIndex::build(idx, dict, sts)?;
+        if let Err(e) = t.save(file, idx.len()) {
+            eprintln!("full-text index not saved for {}: {}", file.display(), e);
+        }
+        Ok(t)
+    }
+    fn build(idx: &Idx, dict: &Dict, sts: &[u8]) -> Result<TextIndex, DictError> {
+        // read the entries in the order of the .dict file, so each part is read once.
+        let mut entries = Vec::with_capacity(idx.len());
+        for i in 0..idx.len() {
+            let (off, len) = idx.get_offset_length(i)?;
+            entries.push((off, len as usize, i as u32));
+        }
+        entries.sort_unstable();
+        let end = entries.iter().map(|e| e.0 + e.1 as u64).max().unwrap_or(0);
+
+        let mut map: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut buf = Vec::new();
+        let mut buf_start = 0u64;
+        for (off, len, i) in entries {
+            if off < buf_start || off + len as u64 > buf_start + buf.len() as u64 {
+                let n = (len.max(WINDOW) as u64).min(end - off) as usize;
+                match dict.read(off, n) {
+                    Ok(b) => buf = b,
+                    Err(_) => {
+                        buf.clear();
+                        continue;
+                    }
+                }
+                buf_start = off;
+            }
+            let s = (off - buf_start) as usize;
+            for (t, f) in fields(sts, &buf[s..s + len]) {
+                if !is_text(t) {
+                    continue;
+                }
+                for (_, tok) in tokenize(&field_text(t, f)) {
+                    let p = map.entry(tok).or_default();
+                    // the tokens of an entry are added together.
+                    if p.last() != Some(&i) {
+                        p.push(i);
+                    }
+                }
+            }
+        }
+
+        let mut all: Vec<(String, Vec<u32>)> = map.into_iter().collect();
+        all.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        let mut t = TextIndex::default();
+        for (tok, mut pos) in all {
+            pos.sort_unstable();
+            t.tokens.extend(tok.as_bytes());
+            t.token_ends.push(t.tokens.len() as u32);
+            t.postings.extend(pos);
+            t.posting_ends.push(t.postings.len() as u32);
+        }
+        Ok(t)
+    }
+    fn load(file: &path::Path, count: usize) -> Result<TextIndex, DictError> {
+        let (size, secs, nanos) = stamp(file)?;
+        let mut r = BufReader::new(File::open(cache_path(file))?);
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC
+            || read_u64(&mut r)? != size
+            || read_u64(&mut r)? != secs
+            || read_u32(&mut r)? != nanos
+            || read_u32(&mut r)? as usize != count
+        {
+            return Err(DictError::My(String::from("stale full-text index")));
+        }
+        let mut t = TextIndex::default();
+        let mut buf = [0u8; 4];
+        for _ in 0..read_u32(&mut r)? {
+            t.tokens.extend(read_word(&mut r)?);
+            t.token_ends.push(t.tokens.len() as u32);
+            for _ in 0..read_u32(&mut r)? {
+                r.read_exact(&mut buf)?;
+                let p = u32::from_le_bytes(buf);
+                // a broken index would make lookup read out of range.
+                if p as usize >= count {
+                    return Err(DictError::My(String::from("bad full-text index")));
+                }
+                t.postings.push(p);
+            }
+            t.posting_ends.push(t.postings.len() as u32);
+        }
+        Ok(t)
+    }
+    fn save(&self, file: &path::Path, count: usize) -> Result<(), DictError> {
+        let (size, secs, nanos) = stamp(file)?;
+        let cfile = cache_path(file);
+        // a server and a command line run may write the same index at the same time.
+        let mut tmp = cfile.clone().into_os_string();
+        tmp.push(format!(".{}.tmp", std::process::id()));
+        {
+            let mut w = BufWriter::new(File::create(&tmp)?);
+            w.write_all(MAGIC)?;
+            w.write_all(&size.to_le_bytes())?;
+            w.write_all(&secs.to_le_bytes())?;
+            w.write_all(&nanos.to_le_bytes())?;
+            w.write_all(&(count as u32).to_le_bytes())?;
+            w.write_all(&(self.token_ends.len() as u32).to_le_bytes())?;
+            for i in 0..self.token_ends.len() {
+                let tok = self.token(i);
+                w.write_all(&(tok.len() as u32).to_le_bytes())?;
+                w.write_all(tok)?;
+                let pos = self.positions(i);
+                w.write_all(&(pos.len() as u32).to_le_bytes())?;
+                for p in pos.iter() {
+                    w.write_all(&p.to_le_bytes())?;
+                }
+            }
+            w.flush()?;
+        }
+        fs::rename(&tmp, &cfile)?;
+        Ok(())
+    }
+    fn token(&self, i: usize) -> &[u8] {
+        let start = if i == 0 { 0 } else { self.token_ends[i - 1] };
+        &self.tokens[start as usize..self.token_ends[i] as usize]
+    }
+    fn positions(&self, i: usize) -> &[u32] {
+        let start = if i == 0 { 0 } else { self.posting_ends[i - 1] };
+        &self.postings[start as usize..self.posting_ends[i] as usize]
+    }
+    /// the Idx positions of the entries containing `token`.
+    fn positions_of(&self, token: &[u8]) -> &[u32] {
+        let (mut lo, mut hi) = (0usize, self.token_ends.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.token(mid).cmp(token) {
+                Ordering::Less => lo = mid + 1,
+                Ordering::Greater => hi = mid,
+                Ordering::Equal => return self.positions(mid),
+            }
+        }
+        &[]
+    }
+    /// the Idx positions of the entries containing all `tokens`, in Idx order.
+    pub fn find(&self, tokens: &[String]) -> Vec<u32> {
+        let mut found: Option<Vec<u32>> = None;
+        for t in tokens {
+            let pos = self.positions_of(t.as_bytes());
+            found = Some(match found {
+                None => pos.to_vec(),
+                Some(f) => f
+                    .into_iter()
+                    .filter(|p| pos.binary_search(p).is_ok())
+                    .collect(),
+            });
+        }
+        found.unwrap_or_default()
+    }
+}
+
+/// return true if the field type `t` is text to be indexed.
+/// upper case types are binary data, `r` is a list of resource files.
+fn is_text(t: u8) -> bool {
+    t.is_ascii_lowercase() && t != b'r'
+}
+
+/// the plain text of a field of type `t`. the markup of xdxf(x), html(h), pango(g)
+/// and kingsoft(k) is stripped and the entities are decoded.
+pub fn field_text(t: u8, field: &[u8]) -> String {
+    let s = String::from_utf8_lossy(field);
+    if !matches!(t, b'x' | b'h' | b'g' | b'k') {
+        return s.into_owned();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = &s[..];
+    while let Some(i) = rest.find(['<', '&']) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if rest.starts_with('<') {
+            // a tag separates words, like <br>.
+            out.push(' ');
+            rest = match rest.find('>') {
+                Some(j) => &rest[j + 1..],
+                None => "",
+            };
+        } else {
+            // search the bytes, the 12th byte may be inside a multi-byte char.
+            match rest.as_bytes()[..rest.len().min(12)]
+                .iter()
+                .position(|c| *c == b';')
+                .and_then(|j| Some((j, entity(&rest[1..j])?)))
+            {
+                Some((j, c)) => {
+                    out.push(c);
+                    rest = &rest[j + 1..];
+                }
+                None => {
+                    out.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn entity(name: &str) -> Option<char> {
+    match name {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "amp" => Some('&'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let n = name.strip_prefix('#')?;
+            let n = match n.strip_prefix(['x', 'X']) {
+                Some(h) => u32::from_str_radix(h, 16).ok()?,
+                None => n.parse().ok()?,
+            };
+            char::from_u32(n)
+        }
+    }
+}
+
+/// CJK text has no spaces between words, each char is a token.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{20000}'..='\u{2fa1f}')
+}
+
+/// split `text` into lower case tokens of letters and digits, with the position of each.
+pub fn tokenize(text: &str) -> Vec<(Range<usize>, String)> {
+    let mut ret = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        if let Some(s) = start {
+            if !c.is_alphanumeric() || is_cjk(c) {
+                ret.push((s..i, text[s..i].to_lowercase()));
+                start = None;
+            }
+        }
+        if is_cjk(c) {
+            let e = i + c.len_utf8();
+            ret.push((i..e, text[i..e].to_string()));
+        } else if start.is_none() && c.is_alphanumeric() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        ret.push((s..text.len(), text[s..].to_lowercase()));
+    }
+    ret
+}
+
+/// a short text around the first hit of `query` tokens in the definition `data`,
+/// where all of them appear in order if possible. None if there is no hit.
+pub fn snippet(sts: &[u8], data: &[u8], query: &[String]) -> Option<String> {
+    if query.is_empty() {
+        return None;
+    }
+    let mut first = None;
+    for (t, f) in fields(sts, data) {
+        if !is_text(t) {
+            continue;
+        }
+        let text = field_text(t, f);
+        let toks = tokenize(&text);
+        if let Some(p) = toks
+            .windows(query.len())
+            .position(|w| w.iter().zip(query).all(|(a, b)| a.1 == *b))
+        {
+            return Some(around(
+                &text,
+                toks[p].0.start,
+                toks[p + query.len() - 1].0.end,
+            ));
+        }
+        if first.is_none() {
+            first = toks
+                .iter()
+                .find(|x| x.1 == query[0])
+                .map(|x| around(&text, x.0.start, x.0.end));
+        }
+    }
+    first
+}
+
+/// the text in [start, end) with some chars before and after it, spaces collapsed.
+fn around(text: &str, start: usize, end: usize) -> String {
+    let b = text[..start]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CHARS - 1)
+        .map_or(0, |(i, _)| i);
+    let e = text[end..]
+        .char_indices()
+        .nth(SNIPPET_CHARS)
+        .map_or(text.len(), |(i, _)| end + i);
+    let mut s = String::new();
+    if b > 0 {
+        s.push_str("...");
+    }
+    s.push_str(&text[b..e].split_whitespace().collect::<Vec<_>>().join(" "));
+    if e < text.len() {
+        s.push_str("...");
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::field_text;
+
+    #[test]
+    fn field_text_entities() {
+        assert_eq!(field_text(b'h', b"a &lt;b&gt; &amp;&#65;"), "a <b> &A");
+        assert_eq!(field_text(b'h', b"x<br>y &bogus; z"), "x y &bogus; z");
+        assert_eq!(field_text(b'm', b"a &lt; b"), "a &lt; b");
+    }
+
+    #[test]
+    fn field_text_multibyte_after_ampersand() {
+        let s = "a & 你好世界 b";
+        assert_eq!(field_text(b'h', s.as_bytes()), s);
+        assert_eq!(field_text(b'h', "&你好世界;".as_bytes()), "&你好世界;");
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+/// Edit distance matcher, used to suggest near-miss words when a lookup finds nothing.
+/// The distance is the optimal string alignment distance(Levenshtein plus adjacent
+/// transposition, like Damerau), counted in chars, compared case-insensitively.
+/// All buffers are kept between calls, so scanning a whole Idx does not allocate per word.
+pub struct Suggester {
+    query: Vec<char>,
+    max: usize,
+    word: Vec<char>,
+    rows: [Vec<usize>; 3], //the DP rows of i-2, i-1 and i
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+impl Suggester {
+    /// create a matcher for `word`, accepting words within `max` edits.
+    pub fn new(word: &[u8], max: usize) -> Suggester {
+        let query: Vec<char> = String::from_utf8_lossy(word).chars().map(fold).collect();
+        let n = query.len() + 1;
+        Suggester {
+            query,
+            max,
+            word: Vec::new(),
+            rows: [vec![0; n], vec![0; n], vec![0; n]],
+        }
+    }
+    /// the chars of the query, lower cased.
+    pub fn query(&self) -> &[char] {
+        &self.query
+    }
+    /// the max distance accepted.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+    /// return the distance between the query and `w`, or None if it is more than the max.
+    pub fn distance(&mut self, w: &[u8]) -> Option<usize> {
+        let max = self.max;
+        let lq = self.query.len();
+        // a char is at most 4 bytes in utf8, skip hopeless words before decoding.
+        if w.len() + 4 * max < lq || w.len() > 4 * (lq + max) {
+            return None;
+        }
+        self.word.clear();
+        match std::str::from_utf8(w) {
+            Ok(s) => self.word.extend(s.chars().map(fold)),
+            Err(_) => return None,
+        }
+        let lw = self.word.len();
+        if lw + max < lq || lw > lq + max {
+            return None;
+        }
+
+        // rows are indexed by the position in query, one row for each char of word.
+        let inf = max + 1;
+        for (j, c) in self.rows[1].iter_mut().enumerate() {
+            *c = j;
+        }
+        for i in 1..=lw {
+            let (r2, r1, r0) = {
+                let [a, b, c] = &mut self.rows;
+                (a, b, c)
+            };
+            let lo = if i > max { i - max } else { 1 };
+            let hi = (i + max).min(lq);
+            r0[0] = if i <= max { i } else { inf };
+            if lo > 1 {
+                r0[lo - 1] = inf;
+            }
+            let mut row_min = r0[0];
+            for j in lo..=hi {
+                let cost = (self.word[i - 1] != self.query[j - 1]) as usize;
+                let mut d = (r1[j - 1] + cost).min(r1[j] + 1).min(r0[j - 1] + 1);
+                if i > 1
+                    && j > 1
+                    && self.word[i - 1] == self.query[j - 2]
+                    && self.word[i - 2] == self.query[j - 1]
+                {
+                    d = d.min(r2[j - 2] + 1);
+                }
+                let d = d.min(inf);
+                r0[j] = d;
+                row_min = row_min.min(d);
+            }
+            if hi < lq {
+                r0[hi + 1] = inf;
+            }
+            if row_min > max {
+                return None;
+            }
+            self.rows.rotate_left(1);
+        }
+        // after the rotation, the last row is rows[1].
+        let d = self.rows[1][lq];
+        if d <= max {
+            Some(d)
+        } else {
+            None
+        }
+    }
+}
+
+// the marks around a word, so that its first and last chars make bigrams too.
+const START: char = '\u{2}';
+const END: char = '\u{3}';
+
+/// the distinct bigrams of `chars`, with the start and end marks.
+fn bigrams(chars: &[char]) -> Vec<(char, char)> {
+    let mut ret: Vec<(char, char)> = Vec::with_capacity(chars.len() + 1);
+    let mut prev = START;
+    for c in chars.iter().copied().chain(std::iter::once(END)) {
+        ret.push((prev, c));
+        prev = c;
+    }
+    ret.sort_unstable();
+    ret.dedup();
+    ret
+}
+
+/// The candidate index of the words of a dictionary, so that a suggestion computes the
+/// edit distance of a short list instead of every word.
+/// an edit breaks at most 2 bigrams of a word, a swap of two chars 3, so a word within
+/// `max` edits shares all but `3 * max` of the distinct bigrams of the query. a query too
+/// short for that checks the words of a near length instead, those sharing more bigrams
+/// first.
+#[derive(Default)]
+pub struct SuggestIndex {
+    lens: Vec<u8>,                          //the char count of each word, at most 255
+    by_len: Vec<u32>,                       //the words sorted by their char count
+    len_start: Vec<u32>,                    //the start in by_len of each char count
+    grams: HashMap<(char, char), Vec<u32>>, //the words with each bigram, ascending
+}
+
+impl SuggestIndex {
+    /// build the index of `words`, each is identified by its position.
+    pub fn new<'a, I: Iterator<Item = &'a [u8]>>(words: I) -> SuggestIndex {
+        let mut t = SuggestIndex::default();
+        let mut chars = Vec::new();
+        for (i, w) in words.enumerate() {
+            chars.clear();
+            chars.extend(String::from_utf8_lossy(w).chars().map(fold));
+            t.lens.push(chars.len().min(255) as u8);
+            for g in bigrams(&chars) {
+                t.grams.entry(g).or_default().push(i as u32);
+            }
+        }
+        let lens = &t.lens;
+        let mut by_len: Vec<u32> = (0..lens.len() as u32).collect();
+        by_len.sort_by_key(|i| lens[*i as usize]);
+        t.by_len = by_len;
+        t.len_start = vec![0; 257];
+        for l in t.lens.iter() {
+            t.len_start[*l as usize + 1] += 1;
+        }
+        for l in 1..t.len_start.len() {
+            t.len_start[l] += t.len_start[l - 1];
+        }
+        t
+    }
+    /// the positions of the words which may be within the distance of `sug`, the most
+    /// likely first, at most `cap`.
+    pub fn candidates(&self, sug: &Suggester, cap: usize) -> Vec<u32> {
+        let query = sug.query();
+        let max = sug.max();
+        let lq = query.len();
+        let lo = lq.saturating_sub(max).min(255);
+        let hi = (lq + max).min(255);
+        let grams = bigrams(query);
+        let need = grams.len().saturating_sub(3 * max);
+        let mut count = vec![0u16; self.lens.len()];
+        let mut ret = Vec::new();
+        for g in grams.iter() {
+            for i in self.grams.get(g).map_or(&[][..], |v| v) {
+                let c = &mut count[*i as usize];
+                *c += 1;
+                let l = self.lens[*i as usize] as usize;
+                if *c as usize == need && l >= lo && l <= hi {
+                    ret.push(*i);
+                }
+            }
+        }
+        if need == 0 {
+            // any word of a near length may do, the ones sharing more bigrams first.
+            let r = self.len_start[lo] as usize..self.len_start[hi + 1] as usize;
+            ret = self.by_len[r].to_vec();
+        }
+        ret.sort_by_key(|i| std::cmp::Reverse(count[*i as usize]));
+        ret.truncate(cap);
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // the optimal string alignment distance by the full table, without any cutoff.
+    fn osa(a: &str, b: &str) -> usize {
+        let a: Vec<char> = a.chars().map(fold).collect();
+        let b: Vec<char> = b.chars().map(fold).collect();
+        let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+        for (i, row) in d.iter_mut().enumerate() {
+            row[0] = i;
+        }
+        d[0] = (0..=b.len()).collect();
+        for i in 1..=a.len() {
+            for j in 1..=b.len() {
+                let cost = (a[i - 1] != b[j - 1]) as usize;
+                d[i][j] = (d[i - 1][j - 1] + cost)
+                    .min(d[i - 1][j] + 1)
+                    .min(d[i][j - 1] + 1);
+                if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                    d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+                }
+            }
+        }
+        d[a.len()][b.len()]
+    }
+
+    // all words of up to `n` chars of `alphabet`.
+    fn words(alphabet: &[char], n: usize) -> Vec<String> {
+        let mut ret = vec![String::new()];
+        let mut last = vec![String::new()];
+        for _ in 0..n {
+            last = last
+                .iter()
+                .flat_map(|w| alphabet.iter().map(move |c| format!("{}{}", w, c)))
+                .collect();
+            ret.extend(last.iter().cloned());
+        }
+        ret
+    }
+
+    #[test]
+    fn distance_equals_osa() {
+        let all = words(&['a', 'b', 'É', 'é', 'я'], 4);
+        for q in all.iter().step_by(11) {
+            for max in 0..=3 {
+                let mut sug = Suggester::new(q.as_bytes(), max);
+                for w in all.iter() {
+                    let d = osa(q, w);
+                    let expect = if d <= max { Some(d) } else { None };
+                    assert_eq!(sug.distance(w.as_bytes()), expect, "{} {} {}", q, w, max);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn distance_cases() {
+        let mut sug = Suggester::new(b"color", 1);
+        assert_eq!(sug.distance(b"colour"), Some(1));
+        assert_eq!(sug.distance(b"COLOR"), Some(0));
+        assert_eq!(sug.distance(b"clolr"), None);
+        assert_eq!(sug.distance(b"ocolr"), None);
+        let mut sug = Suggester::new(b"color", 2);
+        assert_eq!(sug.distance(b"oclro"), Some(2));
+        assert_eq!(sug.distance(b"colorful"), None);
+        assert_eq!(sug.distance(b""), None);
+        assert_eq!(sug.distance(b"col\xffr"), None);
+        let mut sug = Suggester::new(b"", 2);
+        assert_eq!(sug.distance(b""), Some(0));
+        assert_eq!(sug.distance("éa".as_bytes()), Some(2));
+        assert_eq!(sug.distance(b"abc"), None);
+        let mut sug = Suggester::new("москва".as_bytes(), 1);
+        assert_eq!(sug.distance("МОСКАВ".as_bytes()), Some(1));
+        assert_eq!(sug.distance("моска".as_bytes()), Some(1));
+    }
+
+    #[test]
+    fn candidates_include_all_matches() {
+        // pseudo random words of 1 to 12 chars.
+        let alphabet = ['a', 'b', 'c', 'd', 'e', 'ö', 'ж'];
+        let mut seed = 12345u32;
+        let mut next = |n: usize| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as usize % n
+        };
+        let mut list = Vec::new();
+        for _ in 0..2000 {
+            let len = 1 + next(12);
+            list.push(
+                (0..len)
+                    .map(|_| alphabet[next(alphabet.len())])
+                    .collect::<String>(),
+            );
+        }
+        let index = SuggestIndex::new(list.iter().map(|w| w.as_bytes()));
+        let mut queries: Vec<String> = list.iter().step_by(97).cloned().collect();
+        queries.extend(["", "a", "abcdeöжabcd"].iter().map(|s| s.to_string()));
+        for q in queries.iter() {
+            for max in 0..=3 {
+                let sug = Suggester::new(q.as_bytes(), max);
+                let found: HashSet<u32> = index.candidates(&sug, usize::MAX).into_iter().collect();
+                for (i, w) in list.iter().enumerate() {
+                    if osa(q, w) <= max {
+                        assert!(found.contains(&(i as u32)), "{} {} {}", q, w, max);
+                    }
+                }
+                assert!(index.candidates(&sug, 5).len() <= 5);
+            }
+        }
+    }
+}
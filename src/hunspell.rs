@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::{fs, path};
+
+use super::result::DictError;
+
+/// how flags are written in .aff and .dic files, the FLAG option.
+#[derive(Clone, Copy, PartialEq)]
+enum FlagMode {
+    Char, //one char each, the default
+    Long, //two chars each
+    Num,  //decimal numbers separated by ','
+}
+
+/// one char of an affix condition.
+enum Cond {
+    Any,
+    Char(char),
+    Set(Vec<char>, bool), //the chars and whether the set is negated
+}
+
+/// a PFX or SFX rule of an .aff file.
+struct Rule {
+    flag: u32,
+    cross: bool, //a prefix and a suffix may be combined
+    strip: String,
+    add: String,
+    cont: Vec<u32>, //continuation flags, the affixed word may take these affixes too
+    cond: Vec<Cond>,
+}
+
+/// Hunspell affix rules and word list(.aff and .dic files), used to find the stems
+/// of inflected words of languages other than English.
+/// only the affix rules are used, compounding and suggestion options are ignored.
+pub struct Affix {
+    mode: FlagMode,
+    latin1: bool, //SET ISO8859-1, otherwise UTF-8
+    aliases: Vec<Vec<u32>>,
+    prefixes: Vec<Rule>,
+    suffixes: Vec<Rule>,
+    words: HashMap<String, Vec<u32>>,
+}
+
+fn read_lines(file: &path::Path, latin1: bool) -> Result<Vec<String>, DictError> {
+    let mut ret = Vec::new();
+    for line in io::BufReader::new(fs::File::open(file)?).split(b'\n') {
+        let line = line?;
+        ret.push(if latin1 {
+            line.iter().map(|c| *c as char).collect()
+        } else {
+            String::from_utf8_lossy(&line).into_owned()
+        });
+    }
+    Ok(ret)
+}
+
+fn parse_cond(s: &str) -> Vec<Cond> {
+    let mut ret = Vec::new();
+    let mut it = s.chars();
+    while let Some(c) = it.next() {
+        ret.push(match c {
+            '.' => Cond::Any,
+            '[' => {
+                let mut set = Vec::new();
+                let mut neg = false;
+                for (i, c) in it.by_ref().enumerate() {
+                    match c {
+                        ']' => break,
+                        '^' if i == 0 => neg = true,
+                        c => set.push(c),
+                    }
+                }
+                Cond::Set(set, neg)
+            }
+            c => Cond::Char(c),
+        });
+    }
+    ret
+}
+
+fn cond_match(cond: &[Cond], chars: &[char]) -> bool {
+    cond.len() <= chars.len()
+        && cond.iter().zip(chars).all(|(c, x)| match c {
+            Cond::Any => true,
+            Cond::Char(y) => x == y,
+            Cond::Set(set, neg) => set.contains(x) != *neg,
+        })
+}
+
+impl Rule {
+    /// the root of `word` with this prefix removed.
+    fn strip_prefix(&self, word: &str) -> Option<String> {
+        let rest = word.strip_prefix(self.add.as_str())?;
+        let root = format!("{}{}", self.strip, rest);
+        let chars: Vec<char> = root.chars().take(self.cond.len()).collect();
+        if !rest.is_empty() && cond_match(&self.cond, &chars) {
+            Some(root)
+        } else {
+            None
+        }
+    }
+    /// the root of `word` with this suffix removed.
+    fn strip_suffix(&self, word: &str) -> Option<String> {
+        let rest = word.strip_suffix(self.add.as_str())?;
+        let root = format!("{}{}", rest, self.strip);
+        let mut chars: Vec<char> = root.chars().rev().take(self.cond.len()).collect();
+        chars.reverse();
+        if !rest.is_empty() && cond_match(&self.cond, &chars) {
+            Some(root)
+        } else {
+            None
+        }
+    }
+}
+
+impl Affix {
+    /// load `name`.aff and `name`.dic.
+    pub fn open(aff: &path::Path, dic: &path::Path) -> Result<Affix, DictError> {
+        let mut a = Affix {
+            mode: FlagMode::Char,
+            latin1: false,
+            aliases: Vec::new(),
+            prefixes: Vec::new(),
+            suffixes: Vec::new(),
+            words: HashMap::new(),
+        };
+        // the encoding is set in the file itself, latin1 keeps the bytes to find it.
+        for line in read_lines(aff, true)? {
+            let f: Vec<&str> = line.split_whitespace().collect();
+            if let ["SET", enc, ..] = f.as_slice() {
+                match enc.to_ascii_uppercase().as_str() {
+                    "UTF-8" => (),
+                    "ISO8859-1" | "ISO-8859-1" => a.latin1 = true,
+                    _ => return Err(DictError::My(format!("unsupported encoding {}", enc))),
+                }
+            }
+        }
+        let lines = read_lines(aff, a.latin1)?;
+        let mut cross = HashMap::new();
+        let mut af_count = false;
+        for line in lines.iter() {
+            let f: Vec<&str> = line.split_whitespace().collect();
+            match f.as_slice() {
+                ["FLAG", "long", ..] => a.mode = FlagMode::Long,
+                ["FLAG", "num", ..] => a.mode = FlagMode::Num,
+                // the first AF line is the count of aliases.
+                ["AF", _, ..] if !af_count => af_count = true,
+                ["AF", flags, ..] => {
+                    let flags = a.parse_flags(flags);
+                    a.aliases.push(flags);
+                }
+                // the header of the rules: PFX flag cross_product count.
+                [kind @ ("PFX" | "SFX"), flag, yn @ ("Y" | "N"), _] => {
+                    let flag = a.parse_flags(flag).first().copied().unwrap_or(0);
+                    cross.insert((*kind, flag), *yn == "Y");
+                }
+                [kind @ ("PFX" | "SFX"), flag, strip, add, cond, ..] => {
+                    let (add, cont) = match add.split_once('/') {
+                        Some((add, cont)) => (add, a.flags(cont)),
+                        None => (*add, Vec::new()),
+                    };
+                    let flag = a.parse_flags(flag).first().copied().unwrap_or(0);
+                    let rule = Rule {
+                        flag,
+                        cross: cross.get(&(*kind, flag)).copied().unwrap_or(false),
+                        strip: if *strip == "0" { "" } else { strip }.to_string(),
+                        add: if add == "0" { "" } else { add }.to_string(),
+                        cont,
+                        cond: parse_cond(if *cond == "." { "" } else { cond }),
+                    };
+                    if *kind == "PFX" {
+                        a.prefixes.push(rule);
+                    } else {
+                        a.suffixes.push(rule);
+                    }
+                }
+                _ => (),
+            }
+        }
+        for line in read_lines(dic, a.latin1)?.iter().skip(1) {
+            let entry = match line.trim_end().split(['\t', ' ']).next() {
+                Some(e) if !e.is_empty() => e,
+                _ => continue,
+            };
+            let (word, flags) = match entry.split_once('/') {
+                Some((w, f)) => (w, a.flags(f)),
+                None => (entry, Vec::new()),
+            };
+            a.words.entry(word.to_string()).or_default().extend(flags);
+        }
+        Ok(a)
+    }
+    fn parse_flags(&self, s: &str) -> Vec<u32> {
+        match self.mode {
+            FlagMode::Char => s.chars().map(|c| c as u32).collect(),
+            FlagMode::Long => s
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|c| c.iter().fold(0u32, |a, x| (a << 16) | *x as u32))
+                .collect(),
+            FlagMode::Num => s.split(',').filter_map(|n| n.parse().ok()).collect(),
+        }
+    }
+    /// the flags of a word or an affix, which may be an alias number of AF.
+    fn flags(&self, s: &str) -> Vec<u32> {
+        if !self.aliases.is_empty() {
+            if let Ok(n) = s.parse::<usize>() {
+                return self
+                    .aliases
+                    .get(n.wrapping_sub(1))
+                    .cloned()
+                    .unwrap_or_default();
+            }
+        }
+        self.parse_flags(s)
+    }
+    fn has_flag(&self, word: &str, flag: u32) -> bool {
+        self.words.get(word).is_some_and(|f| f.contains(&flag))
+    }
+    /// the words of the .dic file, which `word` may be inflected from by the affix rules.
+    /// a capitalized word is tried in lower case too.
+    pub fn stems(&self, word: &str) -> Vec<String> {
+        let mut ret = Vec::new();
+        self.stems_of(word, &mut ret);
+        let lower = word.to_lowercase();
+        if lower != word {
+            self.stems_of(&lower, &mut ret);
+        }
+        ret.retain(|s| s != word && *s != lower);
+        ret
+    }
+    fn stems_of(&self, word: &str, ret: &mut Vec<String>) {
+        let mut add = |s: String| {
+            if !ret.contains(&s) {
+                ret.push(s);
+            }
+        };
+        for p in self.prefixes.iter() {
+            if let Some(root) = p.strip_prefix(word) {
+                if self.has_flag(&root, p.flag) {
+                    add(root);
+                }
+            }
+        }
+        for s in self.suffixes.iter() {
+            let root = match s.strip_suffix(word) {
+                Some(r) => r,
+                None => continue,
+            };
+            if self.has_flag(&root, s.flag) {
+                add(root.clone());
+            }
+            // a prefix and a suffix.
+            if s.cross {
+                for p in self.prefixes.iter().filter(|p| p.cross) {
+                    if let Some(r) = p.strip_prefix(&root) {
+                        if self.has_flag(&r, s.flag) && self.has_flag(&r, p.flag) {
+                            add(r);
+                        }
+                    }
+                }
+            }
+            // two suffixes, the inner one allows the outer one by its continuation flags.
+            for s2 in self.suffixes.iter().filter(|x| x.cont.contains(&s.flag)) {
+                if let Some(r) = s2.strip_suffix(&root) {
+                    if self.has_flag(&r, s2.flag) {
+                        add(r);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// the Affix sets of dictionary groups, configured by `hunspell.conf` in the directory:
+/// ```text
+/// # :NAME
+/// # begins a group, using NAME.aff and NAME.dic in the same directory.
+/// # the following lines are the dictionaries in the group, their directories
+/// # relative to the dictionary root. a directory includes all dictionaries in it.
+/// :de_DE
+/// de-en
+/// ```
+pub fn load_groups(dir: &path::Path) -> Result<Vec<(Affix, Vec<String>)>, DictError> {
+    let mut groups: Vec<(Affix, Vec<String>)> = Vec::new();
+    let mut skip = false;
+    for line in read_lines(&dir.join("hunspell.conf"), false)? {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix(':') {
+            let aff = dir.join(format!("{}.aff", name));
+            let dic = dir.join(format!("{}.dic", name));
+            match Affix::open(&aff, &dic) {
+                Ok(a) => {
+                    groups.push((a, Vec::new()));
+                    skip = false;
+                }
+                Err(e) => {
+                    eprintln!("ignore affix {}: {}", name, e);
+                    skip = true;
+                }
+            }
+        } else if let (Some(g), false) = (groups.last_mut(), skip) {
+            g.1.push(line.trim_end_matches('/').to_string());
+        }
+    }
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // write `name`.aff and `name`.dic, and load them.
+    fn affix(name: &str, aff: &str, dic: &[&str]) -> Affix {
+        let dir = std::env::temp_dir();
+        let stem = format!("stardict-{}-{}", std::process::id(), name);
+        let (a, d) = (
+            dir.join(format!("{}.aff", stem)),
+            dir.join(format!("{}.dic", stem)),
+        );
+        fs::write(&a, aff).unwrap();
+        fs::write(&d, format!("{}\n{}\n", dic.len(), dic.join("\n"))).unwrap();
+        let ret = Affix::open(&a, &d).unwrap();
+        fs::remove_file(a).unwrap();
+        fs::remove_file(d).unwrap();
+        ret
+    }
+
+    const EN: &str = "SET UTF-8
+PFX U Y 1
+PFX U 0 un .
+SFX D Y 2
+SFX D y ied [^aeiou]y
+SFX D 0 ed [^y]
+";
+
+    #[test]
+    fn conditions() {
+        let a = affix("cond", EN, &["study/DU", "play/D", "walk/D", "dry"]);
+        assert_eq!(a.stems("studied"), vec!["study"]);
+        assert_eq!(a.stems("walked"), vec!["walk"]);
+        // "play" ends with a vowel and y, neither rule applies.
+        assert!(a.stems("plaied").is_empty());
+        assert!(a.stems("played").is_empty());
+        // "dry" has no flags.
+        assert!(a.stems("dried").is_empty());
+        assert_eq!(a.stems("Studied"), vec!["study"]);
+        assert!(a.stems("study").is_empty());
+    }
+
+    #[test]
+    fn cross_product() {
+        let a = affix("cross", EN, &["study/DU", "walk/D"]);
+        assert_eq!(a.stems("unstudied"), vec!["study"]);
+        assert_eq!(a.stems("unstudy"), vec!["study"]);
+        // "walk" does not take the prefix.
+        assert!(a.stems("unwalked").is_empty());
+    }
+
+    #[test]
+    fn long_flags_and_continuation() {
+        let aff = "FLAG long
+SFX Aa Y 1
+SFX Aa 0 s .
+SFX Bb Y 1
+SFX Bb 0 ly/Aa .
+";
+        let a = affix("long", aff, &["quick/Bb", "dog/Aa", "cat/AB", "fox/A"]);
+        assert_eq!(a.stems("quickly"), vec!["quick"]);
+        assert_eq!(a.stems("quicklys"), vec!["quick"]);
+        assert_eq!(a.stems("dogs"), vec!["dog"]);
+        assert!(a.stems("cats").is_empty());
+        assert!(a.stems("foxs").is_empty());
+        assert!(a.stems("dogly").is_empty());
+    }
+
+    #[test]
+    fn aliases_and_num_flags() {
+        let aff = "FLAG num
+AF 2
+AF 101,102
+AF 102
+SFX 101 Y 1
+SFX 101 0 а .
+SFX 102 Y 1
+SFX 102 0 ы .
+";
+        let a = affix("num", aff, &["москв/1", "мир/2"]);
+        assert_eq!(a.stems("москва"), vec!["москв"]);
+        assert_eq!(a.stems("Москвы"), vec!["москв"]);
+        assert_eq!(a.stems("миры"), vec!["мир"]);
+        assert!(a.stems("мира").is_empty());
+    }
+
+    #[test]
+    fn parse_conditions() {
+        let c = parse_cond("[^aeiou]y.");
+        let m = |s: &str| cond_match(&c, &s.chars().collect::<Vec<_>>());
+        assert!(m("byx") && m("tyy"));
+        assert!(!m("ayx") && !m("bzx") && !m("by"));
+        let c = parse_cond("[ае]");
+        assert!(cond_match(&c, &['е']) && !cond_match(&c, &['о']));
+    }
+}
@@ -1,22 +1,64 @@
+use super::cache::OffsetCache;
 use super::result::DictError;
+use flate2::read::GzDecoder;
+use memmap2::Mmap;
 use std::cmp::Ordering;
 use std::fs::File;
-use std::io::{BufReader, Read};
-use std::os::unix::prelude::FileExt;
+use std::io::Read;
+use std::ops::{Deref, Range};
 use std::path;
 
-// total bytes used for offset and length
-const OFF_LEN_BYTES: usize = 8;
+/// the content of an .idx or .syn file. a plain file is memory mapped or read into memory,
+/// a gzip compressed one(.idx.gz, .syn.gz) is inflated into memory.
+#[derive(Debug)]
+pub enum IdxData {
+    Mapped(Mmap),
+    Inflated(Vec<u8>),
+}
+impl IdxData {
+    /// open `file`, or `file`.gz if `file` does not exist. a plain file is mapped if `mapped`.
+    /// return the data, and the path of the file actually opened.
+    /// a mapped file must not be truncated or rewritten in place while it is open, reading
+    /// the lost pages crashes the process(SIGBUS). replacing it by rename is safe.
+    pub fn open(file: &path::Path, mapped: bool) -> Result<(IdxData, path::PathBuf), DictError> {
+        if file.is_file() && mapped {
+            // the file is mapped read-only, words are borrowed from it without copying.
+            let data = unsafe { Mmap::map(&File::open(file)?)? };
+            return Ok((IdxData::Mapped(data), file.to_path_buf()));
+        }
+        if file.is_file() {
+            let mut data = Vec::new();
+            File::open(file)?.read_to_end(&mut data)?;
+            return Ok((IdxData::Inflated(data), file.to_path_buf()));
+        }
+        let mut gz = file.as_os_str().to_owned();
+        gz.push(".gz");
+        let gz = path::PathBuf::from(gz);
+        let mut data = Vec::new();
+        GzDecoder::new(File::open(&gz)?).read_to_end(&mut data)?;
+        Ok((IdxData::Inflated(data), gz))
+    }
+}
+impl Deref for IdxData {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        match self {
+            IdxData::Mapped(m) => m,
+            IdxData::Inflated(v) => v,
+        }
+    }
+}
 
 /// An .idx file representor.
 /// file format:
 /// 1. utf8-coded string with '\0' ending.
-/// 2. offset in dict file u32 or u64(not used for now)
+/// 2. offset in dict file u32 or u64(when idxoffsetbits=64)
 /// 3. length in dict file u32
 #[derive(Debug)]
 pub struct Idx {
-    filedesc: File,  //file descriptor
-    index: Vec<u32>, //end of each word
+    data: IdxData,        //content of .idx file
+    index: Vec<u32>,      //end of each word
+    off_len_bytes: usize, //total bytes used for offset and length
 
     firstword: Vec<u8>,  //first word
     middleword: Vec<u8>, //middle word
@@ -25,10 +67,10 @@ pub struct Idx {
 }
 
 enum WordPosition {
-    FirstWordPos,
-    MiddleWordPos,
-    LastWordPos,
-    OtherPos,
+    First,
+    Middle,
+    Last,
+    Other,
 }
 enum ParseState {
     Word(WordPosition),
@@ -54,9 +96,9 @@ impl Parser {
                     self.state = ParseState::OffsetLength(0);
                 } else {
                     match pos {
-                        WordPosition::FirstWordPos => self.firstw.push(x),
-                        WordPosition::MiddleWordPos => self.middlew.push(x),
-                        WordPosition::LastWordPos => self.lastw.push(x),
+                        WordPosition::First => self.firstw.push(x),
+                        WordPosition::Middle => self.middlew.push(x),
+                        WordPosition::Last => self.lastw.push(x),
                         _ => (),
                     }
                 }
@@ -66,11 +108,11 @@ impl Parser {
                     ParseState::OffsetLength(*n + 1)
                 } else {
                     if self.result.len() == self.count_m1 {
-                        ParseState::Word(WordPosition::LastWordPos)
+                        ParseState::Word(WordPosition::Last)
                     } else if self.result.len() == self.count_half {
-                        ParseState::Word(WordPosition::MiddleWordPos)
+                        ParseState::Word(WordPosition::Middle)
                     } else {
-                        ParseState::Word(WordPosition::OtherPos)
+                        ParseState::Word(WordPosition::Other)
                     }
                 };
             }
@@ -80,42 +122,67 @@ impl Parser {
 }
 impl Idx {
     /// create Idx struct from a .idx file, with `filesize`, word `count` and some other arguments.
+    /// if the .idx file does not exist, .idx.gz is used, its inflated size must be `filesize`.
+    /// the parsed word table is cached in a sidecar .oft file, and reused while the .idx is unchanged.
+    /// the .idx is memory mapped if `mapped`, see `IdxData::open()`.
     pub fn open(
         file: &path::Path,
-        _filesize: usize,
+        filesize: usize,
         count: usize,
         off_len_bytes: u8,
+        mapped: bool,
     ) -> Result<Idx, DictError> {
-        let mut con = Parser {
-            off_len_bytes_m1: off_len_bytes - 1,
-            state: ParseState::Word(WordPosition::FirstWordPos),
-            count_m1: count - 1,
-            count_half: count / 2,
-            off_word: 0,
-            result: Vec::with_capacity(count),
-            firstw: Vec::new(),
-            middlew: Vec::new(),
-            lastw: Vec::new(),
-        };
-        {
-            let idx_file = File::open(file)?;
-            let buf_rd = BufReader::new(idx_file);
-            buf_rd.bytes().for_each(|x| con.parse(x.unwrap()));
-        }
-
-        if count != con.result.len() {
+        let (data, file) = IdxData::open(file, mapped)?;
+        let file = file.as_path();
+        if file.extension().is_some_and(|e| e == "gz") && data.len() != filesize {
             return Err(DictError::My(format!(
-                "not equal! {} != {}",
-                count,
-                con.result.len()
+                "idxfilesize not equal! {} != {}",
+                filesize,
+                data.len()
             )));
         }
+        let oft = match OffsetCache::load(file, count, off_len_bytes as usize, data.len()) {
+            Ok(c) => c,
+            Err(_) => {
+                let mut con = Parser {
+                    off_len_bytes_m1: off_len_bytes - 1,
+                    state: ParseState::Word(WordPosition::First),
+                    count_m1: count - 1,
+                    count_half: count / 2,
+                    off_word: 0,
+                    result: Vec::with_capacity(count),
+                    firstw: Vec::new(),
+                    middlew: Vec::new(),
+                    lastw: Vec::new(),
+                };
+                data.iter().for_each(|x| con.parse(*x));
+
+                if count != con.result.len() {
+                    return Err(DictError::My(format!(
+                        "not equal! {} != {}",
+                        count,
+                        con.result.len()
+                    )));
+                }
+                let c = OffsetCache {
+                    index: con.result,
+                    firstword: con.firstw,
+                    middleword: con.middlew,
+                    lastword: con.lastw,
+                };
+                if let Err(e) = c.save(file, off_len_bytes as usize) {
+                    eprintln!("offset cache not saved for {}: {}", file.display(), e);
+                }
+                c
+            }
+        };
         Ok(Idx {
-            filedesc: File::open(file)?,
-            index: con.result,
-            firstword: con.firstw,
-            middleword: con.middlew,
-            lastword: con.lastw,
+            data,
+            index: oft.index,
+            off_len_bytes: off_len_bytes as usize,
+            firstword: oft.firstword,
+            middleword: oft.middleword,
+            lastword: oft.lastword,
         })
     }
     /// return the Idx word count.
@@ -124,57 +191,72 @@ impl Idx {
     }
     /// return the word of Idx in the specified position.
     /// Err(DictError) if not found.
-    pub fn get_word(&self, i: usize) -> Result<Vec<u8>, DictError> {
+    pub fn get_word(&self, i: usize) -> Result<&[u8], DictError> {
         //check range first
         if i >= self.index.len() {
             return Err(DictError::NotFound(i));
         }
 
         if i == 0 {
-            return Ok(self.firstword.clone());
+            return Ok(&self.firstword);
         } else if i == self.index.len() - 1 {
-            return Ok(self.lastword.clone());
+            return Ok(&self.lastword);
         } else if i == self.index.len() / 2 {
-            return Ok(self.middleword.clone());
+            return Ok(&self.middleword);
         }
 
         // no i==0 case here.
-        let start = self.index[i - 1] as usize + OFF_LEN_BYTES + 1;
+        let start = self.index[i - 1] as usize + self.off_len_bytes + 1;
         let end = self.index[i] as usize;
         //get data of [start, end)
-        let mut word_result = vec![0u8; end - start];
-        self.filedesc
-            .read_exact_at(&mut word_result, start as u64)?;
-        Ok(word_result)
+        Ok(&self.data[start..end])
     }
 
     /// return the offset and length in .dict file. by the specified position of Idx.
-    pub fn get_offset_length(&self, i: usize) -> Result<(u32, u32), DictError> {
+    /// the offset is u64 when idxoffsetbits=64, otherwise u32 widened to u64.
+    pub fn get_offset_length(&self, i: usize) -> Result<(u64, u32), DictError> {
         //check range first
         if i >= self.index.len() {
             return Err(DictError::NotFound(i));
         }
 
         let start = self.index[i] as usize + 1;
-        let mut buff = [0u8; 8];
-        self.filedesc.read_exact_at(&mut buff, start as u64)?;
-        let offset = u32::from_be_bytes([buff[0], buff[1], buff[2], buff[3]]);
-        let length = u32::from_be_bytes([buff[4], buff[5], buff[6], buff[7]]);
+        let buff = self
+            .data
+            .get(start..start + self.off_len_bytes)
+            .ok_or(DictError::NotFound(i))?;
+        let (off, len) = buff.split_at(self.off_len_bytes - 4);
+        let offset = off.iter().fold(0u64, |a, b| (a << 8) | *b as u64);
+        let length = u32::from_be_bytes([len[0], len[1], len[2], len[3]]);
         Ok((offset, length))
     }
     /// get the position in the Idx. if not found, return Err(usize).
     /// it will first try to do case-sensitive find, it no result,
-    /// try again with case-insensitive find.
+    /// try again with case-insensitive find, at last with Unicode case folding.
     /// the result Err(usize) is used for neighborhood hint.
     pub fn get(&self, word: &[u8]) -> Result<usize, usize> {
-        if Idx::dict_cmp(&self.firstword, word, true) == Ordering::Greater {
-            return Err(0);
-        }
-        if Idx::dict_cmp(&self.lastword, word, true) == Ordering::Less {
-            return Err(self.index.len());
+        let ret = if Idx::dict_cmp(&self.firstword, word, true) == Ordering::Greater {
+            Err(0)
+        } else if Idx::dict_cmp(&self.lastword, word, true) == Ordering::Less {
+            Err(self.index.len())
+        } else {
+            self.binary_search(word, false)
+                .or_else(|_| self.binary_search(word, true))
+        };
+        // only non-ASCII chars may have case variants the binary search can not find.
+        if word.is_ascii() {
+            return ret;
         }
-        self.binary_search(word, false)
-            .or_else(|_| self.binary_search(word, true))
+        ret.or_else(|e| self.get_all(word).first().copied().ok_or(e))
+    }
+    /// get the positions of all words equal to `word` with Unicode case folding, see `fold_cmp`.
+    pub fn get_all(&self, word: &[u8]) -> Vec<usize> {
+        fold_matches(self.index.len(), word, |i| self.get_word(i).unwrap())
+    }
+    /// get the positions of all words beginning with `prefix`, ignoring ASCII case.
+    /// the range is found by binary search, so it is empty if nothing matches.
+    pub fn prefix_range(&self, prefix: &[u8]) -> Range<usize> {
+        prefix_range(self.index.len(), prefix, |i| self.get_word(i).unwrap())
     }
     fn binary_search(&self, word: &[u8], ignore_case: bool) -> Result<usize, usize> {
         let mut size = self.index.len();
@@ -185,18 +267,29 @@ impl Idx {
             // mid is always in [0, size), that means mid is >= 0 and < size.
             // mid >= 0: by definition
             // mid < size: mid = size / 2 + size / 4 + size / 8 ...
-            let cmp = Idx::dict_cmp(&self.get_word(mid).unwrap(), word, ignore_case);
+            let cmp = Idx::dict_cmp(self.get_word(mid).unwrap(), word, ignore_case);
             base = if cmp == Ordering::Greater { base } else { mid };
             size -= half;
         }
         // base is always in [0, size) because base <= mid.
-        let cmp = Idx::dict_cmp(&self.get_word(base).unwrap(), word, ignore_case);
+        let cmp = Idx::dict_cmp(self.get_word(base).unwrap(), word, ignore_case);
         if cmp == Ordering::Equal {
             Ok(base)
         } else {
             Err(base + (cmp == Ordering::Less) as usize)
         }
     }
+    /// compare words with Unicode case folding, so "Москва" equals "москва".
+    /// it is NOT the order of .idx files, which is `dict_cmp`, only use it for equality.
+    pub fn fold_cmp(w1: &[u8], w2: &[u8]) -> Ordering {
+        let l1 = String::from_utf8_lossy(w1);
+        let l2 = String::from_utf8_lossy(w2);
+        l1.chars()
+            .flat_map(char::to_lowercase)
+            .cmp(l2.chars().flat_map(char::to_lowercase))
+    }
+    /// the order of words in .idx and .syn files: ASCII case-insensitive, then case-sensitive.
+    /// bytes of non-ASCII chars are compared as they are.
     pub fn dict_cmp(w1: &[u8], w2: &[u8], ignore_case: bool) -> Ordering {
         let w1len = w1.len();
         let w2len = w2.len();
@@ -223,11 +316,10 @@ impl Idx {
         let mut case_eq: i32 = 0;
         let mut ci2 = w2.iter();
         for c1 in w1.iter() {
-            let c2: u8;
-            match ci2.next() {
+            let c2: u8 = match ci2.next() {
                 None => return Ordering::Greater,
-                Some(c) => c2 = *c,
-            }
+                Some(c) => *c,
+            };
             let l2 = c2.to_ascii_lowercase();
             let l1 = c1.to_ascii_lowercase();
             if l1 > l2 {
@@ -251,3 +343,217 @@ impl Idx {
         }
     }
 }
+
+/// compare the beginning of `word` to `prefix` in the order of `Idx::dict_cmp`.
+/// Equal means `word` begins with `prefix`, ignoring ASCII case.
+fn prefix_cmp(word: &[u8], prefix: &[u8]) -> Ordering {
+    for (c1, c2) in word.iter().zip(prefix.iter()) {
+        match c1.to_ascii_lowercase().cmp(&c2.to_ascii_lowercase()) {
+            Ordering::Equal => (),
+            o => return o,
+        }
+    }
+    if word.len() < prefix.len() {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    }
+}
+/// the range of the words beginning with `prefix`, in `count` sorted words got by `word_at`.
+pub(crate) fn prefix_range<'a, F>(count: usize, prefix: &[u8], word_at: F) -> Range<usize>
+where
+    F: Fn(usize) -> &'a [u8],
+{
+    // the first position where `pred` is false, `pred` is true then false in [0, count).
+    let partition = |pred: &dyn Fn(usize) -> bool| {
+        let (mut lo, mut hi) = (0usize, count);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if pred(mid) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    };
+    let start = partition(&|i| prefix_cmp(word_at(i), prefix) == Ordering::Less);
+    let end = partition(&|i| prefix_cmp(word_at(i), prefix) != Ordering::Greater);
+    start..end.max(start)
+}
+
+// a range of at most this many words is checked one by one.
+const PROBE: usize = 64;
+
+/// the positions of the words equal to `word` with Unicode case folding, in `count` sorted
+/// words got by `word_at`. the words are sorted by `Idx::dict_cmp`, which folds ASCII only,
+/// so the case variants of a non-ASCII char may be far apart. each variant is searched by
+/// its own prefix range, until the range is small enough to check one by one.
+pub(crate) fn fold_matches<'a, F>(count: usize, word: &[u8], word_at: F) -> Vec<usize>
+where
+    F: Fn(usize) -> &'a [u8],
+{
+    let mut found = Vec::new();
+    probe(
+        count,
+        word,
+        &String::from_utf8_lossy(word),
+        Vec::new(),
+        &word_at,
+        &mut found,
+    );
+    found.sort_unstable();
+    found.dedup();
+    found
+}
+fn probe<'a, F>(
+    count: usize,
+    word: &[u8],
+    rest: &str,
+    mut prefix: Vec<u8>,
+    word_at: &F,
+    found: &mut Vec<usize>,
+) where
+    F: Fn(usize) -> &'a [u8],
+{
+    let range = prefix_range(count, &prefix, word_at);
+    if range.len() <= PROBE || rest.is_empty() {
+        for i in range {
+            let w = word_at(i);
+            // the words as long as the whole prefix come first, all of them are checked.
+            if rest.is_empty() && w.len() > prefix.len() {
+                break;
+            }
+            if Idx::fold_cmp(w, word) == Ordering::Equal {
+                found.push(i);
+            }
+        }
+        return;
+    }
+    let mut chars = rest.chars();
+    let c = chars.next().unwrap();
+    let rest = chars.as_str();
+    if c.is_ascii() {
+        // the prefix range ignores ASCII case already.
+        prefix.push(c as u8);
+        return probe(count, word, rest, prefix, word_at, found);
+    }
+    let mut variants: Vec<String> = vec![
+        c.to_string(),
+        c.to_lowercase().collect(),
+        c.to_uppercase().collect(),
+    ];
+    variants.sort_unstable();
+    variants.dedup();
+    for v in variants {
+        let mut p = prefix.clone();
+        p.extend(v.as_bytes());
+        probe(count, word, rest, p, word_at, found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the words sorted in the order of the .idx files.
+    fn sorted(words: &[String]) -> Vec<&[u8]> {
+        let mut ret: Vec<&[u8]> = words.iter().map(|w| w.as_bytes()).collect();
+        ret.sort_by(|a, b| Idx::dict_cmp(a, b, false));
+        ret
+    }
+    fn matches(list: &[&[u8]], word: &str) -> Vec<String> {
+        fold_matches(list.len(), word.as_bytes(), |i| list[i])
+            .into_iter()
+            .map(|i| String::from_utf8_lossy(list[i]).into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn compare() {
+        assert_eq!(
+            Idx::fold_cmp("Москва".as_bytes(), "москва".as_bytes()),
+            Ordering::Equal
+        );
+        assert_eq!(
+            Idx::fold_cmp("ÉTÉ".as_bytes(), "été".as_bytes()),
+            Ordering::Equal
+        );
+        assert_ne!(
+            Idx::fold_cmp("Москва".as_bytes(), "Москвы".as_bytes()),
+            Ordering::Equal
+        );
+        assert_eq!(Idx::dict_cmp(b"Abc", b"abc", true), Ordering::Equal);
+        assert_eq!(Idx::dict_cmp(b"Abc", b"abc", false), Ordering::Less);
+        assert_eq!(Idx::dict_cmp(b"ab", b"abc", true), Ordering::Less);
+        // only ASCII is folded in the file order.
+        assert_ne!(
+            Idx::dict_cmp("М".as_bytes(), "м".as_bytes(), true),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn prefix_ranges() {
+        let words: Vec<String> = ["ab", "Abc", "abd", "b", "ba", "Б", "Ба"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let list = sorted(&words);
+        let range = |p: &str| {
+            prefix_range(list.len(), p.as_bytes(), |i| list[i])
+                .map(|i| String::from_utf8_lossy(list[i]).into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(range("AB"), vec!["ab", "Abc", "abd"]);
+        assert_eq!(range("b"), vec!["b", "ba"]);
+        assert_eq!(range("Б"), vec!["Б", "Ба"]);
+        assert!(range("б").is_empty());
+        assert!(range("c").is_empty());
+        assert_eq!(range("").len(), 7);
+    }
+
+    #[test]
+    fn fold_matches_far_apart() {
+        // the case variants are far apart in the file order, with many words between.
+        let mut words: Vec<String> = (0..200).map(|i| format!("Мо{:03}", i)).collect();
+        words.extend((0..200).map(|i| format!("москва{:03}", i)));
+        words.extend(
+            ["Москва", "москва", "МОСКВА", "Мост"]
+                .iter()
+                .map(|s| s.to_string()),
+        );
+        let list = sorted(&words);
+        let mut found = matches(&list, "москва");
+        found.sort();
+        assert_eq!(found, vec!["МОСКВА", "Москва", "москва"]);
+        assert_eq!(matches(&list, "мост"), vec!["Мост"]);
+        assert!(matches(&list, "москв").is_empty());
+    }
+
+    #[test]
+    fn fold_matches_more_than_probe() {
+        // 128 ASCII case variants of "abcdefg" before each of "ж" and "Ж", all equal in the
+        // file order ignoring case, more than PROBE of them.
+        let mut words = Vec::new();
+        for bits in 0..128u32 {
+            let v: String = "abcdefg"
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    if bits & (1 << i) != 0 {
+                        c.to_ascii_uppercase()
+                    } else {
+                        c
+                    }
+                })
+                .collect();
+            words.push(format!("{}ж", v));
+            words.push(format!("{}Ж", v));
+            words.push(format!("{}жx", v));
+        }
+        let list = sorted(&words);
+        assert_eq!(matches(&list, "ABCDEFGж").len(), 256);
+        assert_eq!(matches(&list, "abcdefgжX").len(), 128);
+    }
+}
@@ -67,6 +67,12 @@ impl Ifo {
                 };
             }
         }
+        if it.idxoffsetbits != 32 && it.idxoffsetbits != 64 {
+            return Err(DictError::My(format!(
+                "unsupported idxoffsetbits {}",
+                it.idxoffsetbits
+            )));
+        }
         Ok(it)
     }
 }
@@ -0,0 +1,46 @@
+use std::io::Write;
+
+/// append `s` to `out` as a JSON string, quoted and escaped.
+/// invalid utf8 sequences are replaced by U+FFFD.
+pub fn push_str(out: &mut Vec<u8>, s: &[u8]) {
+    out.push(b'"');
+    for c in String::from_utf8_lossy(s).bytes() {
+        match c {
+            b'"' => out.extend(b"\\\""),
+            b'\\' => out.extend(b"\\\\"),
+            b'\n' => out.extend(b"\\n"),
+            b'\r' => out.extend(b"\\r"),
+            b'\t' => out.extend(b"\\t"),
+            0..=0x1f => {
+                write!(out, "\\u{:04x}", c).ok();
+            }
+            _ => out.push(c),
+        }
+    }
+    out.push(b'"');
+}
+
+/// append `"key":` to `out`, the key is written as is.
+pub fn push_key(out: &mut Vec<u8>, key: &str) {
+    out.push(b'"');
+    out.extend(key.as_bytes());
+    out.extend(b"\":");
+}
+
+/// append a `"key":"value"` pair to `out`.
+pub fn push_pair(out: &mut Vec<u8>, key: &str, value: &[u8]) {
+    push_key(out, key);
+    push_str(out, value);
+}
+
+/// append a `"key":number` pair to `out`.
+pub fn push_num(out: &mut Vec<u8>, key: &str, value: u64) {
+    push_key(out, key);
+    write!(out, "{}", value).ok();
+}
+
+/// append a `"key":true` or `"key":false` pair to `out`.
+pub fn push_bool(out: &mut Vec<u8>, key: &str, value: bool) {
+    push_key(out, key);
+    out.extend(if value { &b"true"[..] } else { &b"false"[..] });
+}
@@ -0,0 +1,290 @@
+/// irregular English word forms and their base forms.
+static IRREGULAR: &[(&str, &str)] = &[
+    ("ate", "eat"),
+    ("bade", "bid"),
+    ("beaten", "beat"),
+    ("became", "become"),
+    ("began", "begin"),
+    ("begun", "begin"),
+    ("bent", "bend"),
+    ("best", "good"),
+    ("better", "good"),
+    ("bitten", "bite"),
+    ("bled", "bleed"),
+    ("blew", "blow"),
+    ("blown", "blow"),
+    ("bore", "bear"),
+    ("born", "bear"),
+    ("borne", "bear"),
+    ("bought", "buy"),
+    ("bound", "bind"),
+    ("bred", "breed"),
+    ("broke", "break"),
+    ("broken", "break"),
+    ("brought", "bring"),
+    ("built", "build"),
+    ("burnt", "burn"),
+    ("cacti", "cactus"),
+    ("came", "come"),
+    ("caught", "catch"),
+    ("children", "child"),
+    ("chose", "choose"),
+    ("chosen", "choose"),
+    ("clung", "cling"),
+    ("criteria", "criterion"),
+    ("data", "datum"),
+    ("dealt", "deal"),
+    ("did", "do"),
+    ("done", "do"),
+    ("drank", "drink"),
+    ("drawn", "draw"),
+    ("dreamt", "dream"),
+    ("drew", "draw"),
+    ("driven", "drive"),
+    ("drove", "drive"),
+    ("drunk", "drink"),
+    ("dug", "dig"),
+    ("dwelt", "dwell"),
+    ("eaten", "eat"),
+    ("fallen", "fall"),
+    ("feet", "foot"),
+    ("fell", "fall"),
+    ("felt", "feel"),
+    ("fled", "flee"),
+    ("flew", "fly"),
+    ("flown", "fly"),
+    ("forbade", "forbid"),
+    ("forbidden", "forbid"),
+    ("forgave", "forgive"),
+    ("forgiven", "forgive"),
+    ("forgot", "forget"),
+    ("forgotten", "forget"),
+    ("fought", "fight"),
+    ("found", "find"),
+    ("froze", "freeze"),
+    ("frozen", "freeze"),
+    ("fungi", "fungus"),
+    ("gave", "give"),
+    ("geese", "goose"),
+    ("given", "give"),
+    ("gone", "go"),
+    ("got", "get"),
+    ("gotten", "get"),
+    ("grew", "grow"),
+    ("grown", "grow"),
+    ("had", "have"),
+    ("has", "have"),
+    ("heard", "hear"),
+    ("held", "hold"),
+    ("hid", "hide"),
+    ("hidden", "hide"),
+    ("hung", "hang"),
+    ("is", "be"),
+    ("kept", "keep"),
+    ("knelt", "kneel"),
+    ("knew", "know"),
+    ("known", "know"),
+    ("laid", "lay"),
+    ("lain", "lie"),
+    ("lay", "lie"),
+    ("led", "lead"),
+    ("leant", "lean"),
+    ("leapt", "leap"),
+    ("learnt", "learn"),
+    ("left", "leave"),
+    ("lent", "lend"),
+    ("lice", "louse"),
+    ("lit", "light"),
+    ("lost", "lose"),
+    ("made", "make"),
+    ("meant", "mean"),
+    ("men", "man"),
+    ("met", "meet"),
+    ("mice", "mouse"),
+    ("oxen", "ox"),
+    ("paid", "pay"),
+    ("people", "person"),
+    ("phenomena", "phenomenon"),
+    ("ran", "run"),
+    ("rang", "ring"),
+    ("ridden", "ride"),
+    ("risen", "rise"),
+    ("rode", "ride"),
+    ("rose", "rise"),
+    ("rung", "ring"),
+    ("said", "say"),
+    ("sang", "sing"),
+    ("sank", "sink"),
+    ("sat", "sit"),
+    ("saw", "see"),
+    ("seen", "see"),
+    ("sent", "send"),
+    ("shaken", "shake"),
+    ("shook", "shake"),
+    ("shot", "shoot"),
+    ("shown", "show"),
+    ("shrank", "shrink"),
+    ("shrunk", "shrink"),
+    ("slept", "sleep"),
+    ("slid", "slide"),
+    ("smelt", "smell"),
+    ("sold", "sell"),
+    ("sought", "seek"),
+    ("spat", "spit"),
+    ("spent", "spend"),
+    ("spoke", "speak"),
+    ("spoken", "speak"),
+    ("sprang", "spring"),
+    ("stole", "steal"),
+    ("stolen", "steal"),
+    ("stood", "stand"),
+    ("struck", "strike"),
+    ("stuck", "stick"),
+    ("stung", "sting"),
+    ("sung", "sing"),
+    ("sunk", "sink"),
+    ("swam", "swim"),
+    ("swore", "swear"),
+    ("sworn", "swear"),
+    ("swum", "swim"),
+    ("swung", "swing"),
+    ("taken", "take"),
+    ("taught", "teach"),
+    ("teeth", "tooth"),
+    ("thought", "think"),
+    ("threw", "throw"),
+    ("thrown", "throw"),
+    ("told", "tell"),
+    ("took", "take"),
+    ("tore", "tear"),
+    ("torn", "tear"),
+    ("understood", "understand"),
+    ("was", "be"),
+    ("went", "go"),
+    ("were", "be"),
+    ("wept", "weep"),
+    ("women", "woman"),
+    ("won", "win"),
+    ("wore", "wear"),
+    ("worn", "wear"),
+    ("worse", "bad"),
+    ("worst", "bad"),
+    ("wound", "wind"),
+    ("woke", "wake"),
+    ("woken", "wake"),
+    ("wove", "weave"),
+    ("woven", "weave"),
+    ("written", "write"),
+    ("wrote", "write"),
+];
+
+/// inflection suffixes and their replacements, tried in order.
+static SUFFIXES: &[(&str, &str)] = &[
+    ("ies", "y"),
+    ("ied", "y"),
+    ("iest", "y"),
+    ("ier", "y"),
+    ("ily", "y"),
+    ("sses", "ss"),
+    ("ches", "ch"),
+    ("shes", "sh"),
+    ("xes", "x"),
+    ("zes", "z"),
+    ("oes", "o"),
+    ("ves", "f"),
+    ("ves", "fe"),
+    ("men", "man"),
+    ("ing", ""),
+    ("ing", "e"),
+    ("ed", ""),
+    ("ed", "e"),
+    ("es", ""),
+    ("es", "e"),
+    ("s", ""),
+    ("est", ""),
+    ("est", "e"),
+    ("er", ""),
+    ("er", "e"),
+    ("ly", ""),
+];
+
+/// the possible base forms of the English `word`, like "study" of "studied",
+/// "run" of "running" and "mouse" of "mice", the most likely first.
+/// the word itself is not included, and nothing is returned for non-ASCII words.
+pub fn base_forms(word: &[u8]) -> Vec<Vec<u8>> {
+    let mut ret: Vec<Vec<u8>> = Vec::new();
+    if !word.is_ascii() {
+        return ret;
+    }
+    let word = word.to_ascii_lowercase();
+    let w = String::from_utf8_lossy(&word);
+    let mut add = |base: String| {
+        let base = base.into_bytes();
+        if base != word && !ret.contains(&base) {
+            ret.push(base);
+        }
+    };
+    if let Some((_, b)) = IRREGULAR.iter().find(|(f, _)| *f == w) {
+        add(b.to_string());
+    }
+    for (suf, rep) in SUFFIXES.iter() {
+        let stem = match w.strip_suffix(suf) {
+            Some(s) if s.len() >= 2 => s,
+            _ => continue,
+        };
+        add(format!("{}{}", stem, rep));
+        // running -> run, stopped -> stop, bigger -> big.
+        let b = stem.as_bytes();
+        if rep.is_empty()
+            && matches!(*suf, "ing" | "ed" | "er" | "est")
+            && b.len() >= 3
+            && b[b.len() - 1] == b[b.len() - 2]
+            && !b"aeiou".contains(&b[b.len() - 1])
+        {
+            add(stem[..stem.len() - 1].to_string());
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::base_forms;
+
+    fn forms(word: &str) -> Vec<String> {
+        base_forms(word.as_bytes())
+            .into_iter()
+            .map(|b| String::from_utf8(b).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn regular_forms() {
+        assert_eq!(forms("studied")[0], "study");
+        assert_eq!(forms("Studies")[0], "study");
+        assert!(forms("running").contains(&"run".to_string()));
+        assert!(forms("stopped").contains(&"stop".to_string()));
+        assert!(forms("making").contains(&"make".to_string()));
+        assert!(forms("boxes").contains(&"box".to_string()));
+        assert!(forms("wolves").contains(&"wolf".to_string()));
+        assert!(forms("knives").contains(&"knife".to_string()));
+        // a vowel is not doubled, "seeing" is not "se".
+        assert!(!forms("seeing").contains(&"se".to_string()));
+    }
+
+    #[test]
+    fn irregular_forms() {
+        assert_eq!(forms("mice")[0], "mouse");
+        assert_eq!(forms("went")[0], "go");
+        assert_eq!(forms("Children")[0], "child");
+    }
+
+    #[test]
+    fn no_forms() {
+        assert!(forms("go").is_empty());
+        assert!(forms("москвы").is_empty());
+        // the stem must keep 2 chars at least.
+        assert!(!forms("is").contains(&"i".to_string()));
+        assert!(!forms("study").contains(&"study".to_string()));
+    }
+}
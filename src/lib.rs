@@ -0,0 +1,504 @@
+//! StarDict dictionary reader.
+//!
+//! load every dictionary under a directory, then lookup words, list neighbor words,
+//! search with regular expressions or search the definitions, without the web server.
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! let dict = stardict::StarDict::new(Path::new("/usr/share/stardict/dic")).unwrap();
+//! for r in dict.lookup(b"hello").unwrap() {
+//!     for (_, text) in r.entry.texts() {
+//!         println!("{}: {}", r.dictionary.name, String::from_utf8_lossy(text));
+//!     }
+//! }
+//! ```
+
+//extern crate regex;
+
+mod cache;
+mod dict;
+mod dictionary;
+mod entry;
+mod fulltext;
+mod fuzzy;
+mod hunspell;
+mod idx;
+mod ifo;
+mod lemma;
+mod normalize;
+mod resource;
+mod result;
+mod syn;
+
+pub use dictionary::{glob_regex, Dictionary, LookupResult, MatchKind, TextHit};
+pub use entry::Entry;
+pub use ifo::Ifo;
+pub use result::DictError;
+
+use regex::bytes::Regex;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::iter::Iterator;
+use std::mem;
+use std::sync::Arc;
+use std::{fs, path};
+
+// the depth of the sub directories searched for dictionaries.
+const MAX_DEPTH: usize = 16;
+// the edit distances computed for a suggestion, shared by all dictionaries.
+const SUGGEST_CANDIDATES: usize = 20000;
+
+/// StarDict contains all dictionary found within the specified file system directory.
+pub struct StarDict {
+    root: path::PathBuf,
+    mapped: bool, //whether the .idx and .syn files are memory mapped
+    directories: Vec<Arc<dictionary::Dictionary>>,
+    // the .ifo files scanned, and the stamps of their dictionary files, see `refresh()`.
+    scanned: Vec<(path::PathBuf, Vec<dictionary::FileStamp>)>,
+    // Hunspell affix sets, the directories of each from hunspell.conf, and the positions
+    // in directories of the dictionaries using each.
+    affixes: Vec<(Arc<hunspell::Affix>, Vec<String>, Vec<usize>)>,
+}
+
+/// An iterator that merges several underlying iterators. try to dedup one duplicated
+/// word from each iterator. words are borrowed from the underlying Idx and Syn.
+/// the words are merged in the order of `Idx::dict_cmp`, the order of the files. the
+/// current words equal with Unicode case folding(`Idx::fold_cmp`), like "Москва" of one
+/// dictionary and "москва" of another, are listed once, the first in that order.
+pub struct WordMergeIter<'a, T: Iterator<Item = &'a [u8]>> {
+    wordit: Vec<T>,
+    cur: Vec<Option<&'a [u8]>>,
+}
+impl<'a, T: Iterator<Item = &'a [u8]>> Iterator for WordMergeIter<'a, T> {
+    type Item = &'a [u8];
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_source().map(|(_, w)| w)
+    }
+}
+impl<'a, T: Iterator<Item = &'a [u8]>> WordMergeIter<'a, T> {
+    /// same as `next()`, also return the position of the underlying iterator the word comes from.
+    /// see `StarDict::source_info()`.
+    pub fn next_with_source(&mut self) -> Option<(usize, &'a [u8])> {
+        let l = self.cur.len();
+        if l == 0 {
+            return None;
+        }
+
+        let mut x = 0usize;
+        let mut i = 1usize;
+        while i < l {
+            x = match (&self.cur[x], &self.cur[i]) {
+                (None, _) => i,
+                (_, None) => x,
+                (Some(a), Some(b)) => {
+                    let order = idx::Idx::dict_cmp(a, b, false);
+                    if order == Ordering::Equal || idx::Idx::fold_cmp(a, b) == Ordering::Equal {
+                        // skip the later one, the rest of its iterator is after it.
+                        if order == Ordering::Greater {
+                            self.cur[x] = self.wordit[x].next();
+                            i
+                        } else {
+                            self.cur[i] = self.wordit[i].next();
+                            x
+                        }
+                    } else if order == Ordering::Greater {
+                        i
+                    } else {
+                        x
+                    }
+                }
+            };
+            i += 1;
+        }
+        let w = mem::replace(&mut self.cur[x], self.wordit[x].next())?;
+        Some((x, w))
+    }
+}
+
+impl StarDict {
+    /// Create a StarDict struct from a system path. all .ifo files in the path and its
+    /// sub directories are loaded, with the .idx, .dict, etc. of the same name next to
+    /// each. The dictionaries are sorted by their path, and a dictionary with the same
+    /// bookname as one before is ignored.
+    /// the .idx and .syn files are memory mapped, see `open()`.
+    pub fn new(root: &path::Path) -> Result<StarDict, result::DictError> {
+        StarDict::open(root, true)
+    }
+    /// same as `new()`, the .idx and .syn files are memory mapped only if `mapped`, or read
+    /// into memory. a mapped file truncated or rewritten in place crashes the process when
+    /// it is read(SIGBUS). replace the files by rename instead, the mapped old file stays
+    /// readable, and `refresh()` opens the new one. read them into memory only if they may
+    /// be rewritten in place anyway.
+    pub fn open(root: &path::Path, mapped: bool) -> Result<StarDict, result::DictError> {
+        let scanned = StarDict::scan(root)?;
+        Ok(StarDict {
+            root: root.to_path_buf(),
+            mapped,
+            directories: StarDict::load(root, mapped, &scanned, None),
+            scanned,
+            affixes: Vec::new(),
+        })
+    }
+    /// the .ifo files in `root` and its sub directories sorted, and the stamps of their
+    /// dictionary files.
+    fn scan(
+        root: &path::Path,
+    ) -> Result<Vec<(path::PathBuf, Vec<dictionary::FileStamp>)>, result::DictError> {
+        let mut found = Vec::new();
+        if root.is_dir() {
+            let mut visited = HashSet::new();
+            visited.insert(fs::canonicalize(root)?);
+            StarDict::scan_dir(root, 0, &mut visited, &mut found)?;
+        }
+        found.sort();
+        Ok(found)
+    }
+    /// `visited` has the real paths of the directories scanned, a directory reached again
+    /// by a symbolic link is skipped.
+    fn scan_dir(
+        dir: &path::Path,
+        depth: usize,
+        visited: &mut HashSet<path::PathBuf>,
+        found: &mut Vec<(path::PathBuf, Vec<dictionary::FileStamp>)>,
+    ) -> Result<(), result::DictError> {
+        let mut items = fs::read_dir(dir)?
+            .map(|it| it.map(|it| it.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        // the same directory is always reached by the same path.
+        items.sort();
+        for it in items {
+            if it.is_dir() {
+                if !fs::canonicalize(&it).is_ok_and(|c| visited.insert(c)) {
+                    continue;
+                }
+                if depth >= MAX_DEPTH {
+                    eprintln!("ignore too deep {}", it.display());
+                } else if let Err(e) = StarDict::scan_dir(&it, depth + 1, visited, found) {
+                    eprintln!("ignore {}: {:?}", it.display(), e);
+                }
+            } else if it.extension().is_some_and(|e| e == "ifo") {
+                let stamps = dictionary::Dictionary::stamp(&it);
+                found.push((it, stamps));
+            }
+        }
+        Ok(())
+    }
+    /// open the dictionaries of `scanned`, sharing those of `old` whose files are unchanged.
+    /// the bookname is read from each .ifo first, a dictionary with the same bookname as
+    /// one opened before is ignored without opening its other files.
+    fn load(
+        root: &path::Path,
+        mapped: bool,
+        scanned: &[(path::PathBuf, Vec<dictionary::FileStamp>)],
+        old: Option<&StarDict>,
+    ) -> Vec<Arc<dictionary::Dictionary>> {
+        let mut ret: Vec<Arc<dictionary::Dictionary>> = Vec::with_capacity(scanned.len());
+        for it in scanned.iter() {
+            let name = match ifo::Ifo::open(&it.0, root) {
+                Ok(ifo) => ifo.name,
+                Err(e) => {
+                    eprintln!("ignore reason: {:?}", e);
+                    continue;
+                }
+            };
+            if ret.iter().any(|d| d.ifo.name == name) {
+                eprintln!("ignore duplicated {}: {}", name, it.0.display());
+                continue;
+            }
+            let unchanged = old
+                .filter(|o| o.scanned.contains(it))
+                .and_then(|o| o.directories.iter().find(|d| d.ifo_file() == it.0));
+            if let Some(d) = unchanged {
+                ret.push(Arc::clone(d));
+                continue;
+            }
+            match dictionary::Dictionary::new(&it.0, root, mapped) {
+                Ok(d) => {
+                    ret.push(Arc::new(d));
+                }
+                Err(e) => {
+                    eprintln!("ignore reason: {:?}", e);
+                }
+            }
+        }
+        ret
+    }
+    /// Scan the root directory again. if any dictionary is added, removed, or its files are
+    /// modified, return a new StarDict, with the changed dictionaries opened again and the
+    /// others shared with `self`. None if nothing is changed.
+    /// the duplicated booknames are decided again, a dictionary ignored before is opened
+    /// if the one it duplicated is gone.
+    pub fn refresh(&self) -> Result<Option<StarDict>, result::DictError> {
+        let scanned = StarDict::scan(&self.root)?;
+        if scanned == self.scanned {
+            return Ok(None);
+        }
+        let mut ret = StarDict {
+            root: self.root.clone(),
+            mapped: self.mapped,
+            directories: StarDict::load(&self.root, self.mapped, &scanned, Some(self)),
+            scanned,
+            affixes: Vec::new(),
+        };
+        for (affix, paths, _) in self.affixes.iter() {
+            let members = ret.members(paths);
+            ret.affixes
+                .push((Arc::clone(affix), paths.clone(), members));
+        }
+        Ok(Some(ret))
+    }
+    /// the .ifo files of the dictionaries opened by `refresh()` which returned `self`,
+    /// not shared with `old`.
+    pub fn loaded(&self, old: &StarDict) -> Vec<&path::Path> {
+        self.directories
+            .iter()
+            .filter(|d| !old.directories.iter().any(|o| Arc::ptr_eq(d, o)))
+            .map(|d| d.ifo_file())
+            .collect()
+    }
+    /// Load the Hunspell affix sets configured by `hunspell.conf` in `dir`, see
+    /// `hunspell::load_groups`. `lookup()` uses them to find the stems of an unknown word
+    /// in the dictionaries of each group, instead of the English base forms.
+    pub fn load_hunspell(&mut self, dir: &path::Path) -> Result<(), result::DictError> {
+        for (affix, paths) in hunspell::load_groups(dir)? {
+            let members = self.members(&paths);
+            self.affixes.push((Arc::new(affix), paths, members));
+        }
+        Ok(())
+    }
+    /// the positions of the dictionaries in `paths` or their sub directories.
+    fn members(&self, paths: &[String]) -> Vec<usize> {
+        self.directories
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| {
+                let p = d.ifo.dict_path.as_str();
+                paths
+                    .iter()
+                    .any(|g| p == g || (p.starts_with(g.as_str()) && p[g.len()..].starts_with('/')))
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+    /// Build the indexes of all dictionaries which are otherwise built on their first use,
+    /// see `Dictionary::prepare()`. it may take a while, a server calls it in the background.
+    pub fn prepare(&self) {
+        for d in self.directories.iter() {
+            d.prepare();
+        }
+    }
+    /// Get the Ifo struct, which is parsed from the .ifo file.
+    pub fn info(&self) -> Vec<&ifo::Ifo> {
+        let mut items = Vec::with_capacity(self.directories.len());
+        for it in &self.directories {
+            items.push(&it.ifo);
+        }
+        items
+    }
+    /// Get the field numbered `field` of the definition at `index` of the dictionary
+    /// named `bookname`(see `Ifo::name`), like the picture of a `LookupResult`.
+    /// return the type and content of the field.
+    pub fn field(
+        &self,
+        bookname: &str,
+        index: usize,
+        field: usize,
+    ) -> Result<(u8, Vec<u8>), result::DictError> {
+        // the booknames are unique, see `new()`.
+        let d = self
+            .directories
+            .iter()
+            .find(|d| d.ifo.name == bookname)
+            .ok_or_else(|| result::DictError::My(format!("no dictionary {}", bookname)))?;
+        d.entry(index)?
+            .fields
+            .into_iter()
+            .nth(field)
+            .ok_or(result::DictError::NotFound(field))
+    }
+    /// Read a resource file by its `path` under the dictionary root, like
+    /// `DICT_PATH/res/img/cat.png`, from the resource database(res.rifo, res.ridx and
+    /// res.rdic) of the dictionary in DICT_PATH. None if it is not in any database.
+    pub fn resource(&self, path: &str) -> Option<Vec<u8>> {
+        self.directories.iter().find_map(|d| {
+            let name = if d.ifo.dict_path.is_empty() {
+                path.strip_prefix("res/")
+            } else {
+                path.strip_prefix(d.ifo.dict_path.as_str())?
+                    .strip_prefix("/res/")
+            }?;
+            d.res.as_ref()?.get(name.as_bytes()).ok()
+        })
+    }
+    /// Get the Ifo of the dictionary, which a word from `neighbors()` or `search()` comes from.
+    /// `source` is returned by `WordMergeIter::next_with_source()`.
+    pub fn source_info(&self, source: usize) -> Option<&ifo::Ifo> {
+        // each dictionary has one Idx and one Syn iterator.
+        self.directories.get(source / 2).map(|d| &d.ifo)
+    }
+    /// List the following neighbor words of `word`, from `off`.
+    /// If `off` is a negative number, list from before `-off`.
+    pub fn neighbors(
+        &self,
+        word: &[u8],
+        off: i32,
+    ) -> WordMergeIter<'_, dictionary::DictNeighborIter<'_>> {
+        let mut wordit = Vec::with_capacity(2 * self.directories.len());
+        let mut cur = Vec::with_capacity(2 * self.directories.len());
+        for d in self.directories.iter() {
+            let mut x = d.neighbors(word, off);
+            let mut s = d.neighbors_syn(word, off);
+            cur.push(x.next());
+            cur.push(s.next());
+            wordit.push(x);
+            wordit.push(s);
+        }
+
+        WordMergeIter { wordit, cur }
+    }
+    /// Search from all dictionaries. using the specified regular expression.
+    /// to match the beginning of a word, use `^`, the ending of a word, use `$`.
+    /// when the expression begins with `^` and some literal chars, like `^inter.*`, only the
+    /// words beginning with them(ignoring ASCII case) are found by binary search and matched.
+    pub fn search<'a>(&'a self, reg: &'a Regex) -> WordMergeIter<'a, dictionary::IdxIter<'a>> {
+        let mut wordit = Vec::with_capacity(2 * self.directories.len());
+        let mut cur = Vec::with_capacity(2 * self.directories.len());
+        for d in self.directories.iter() {
+            //println!("in for {}", d.ifo.name.as_str());
+            let mut x = d.search_regex(reg);
+            let mut s = d.search_syn(reg);
+            //println!("created inner iter");
+            cur.push(x.next());
+            cur.push(s.next());
+            //println!("created 1st value");
+            wordit.push(x);
+            wordit.push(s);
+        }
+
+        WordMergeIter { wordit, cur }
+    }
+    /// Suggest words for `word`, which is probably misspelled. collect words of all
+    /// dictionaries within `max_distance` edits, ranked by the distance, at most `limit`.
+    /// the work is bounded, only the likeliest candidates of each dictionary are checked.
+    pub fn suggest(&self, word: &[u8], max_distance: usize, limit: usize) -> Vec<&[u8]> {
+        let mut sug = fuzzy::Suggester::new(word, max_distance);
+        let cap = (SUGGEST_CANDIDATES / self.directories.len().max(1)).max(256);
+        let mut found = Vec::new();
+        for d in self.directories.iter() {
+            d.suggest(&mut sug, cap, &mut found);
+        }
+        found.sort_by(|a, b| a.0.cmp(&b.0).then(idx::Idx::dict_cmp(a.1, b.1, false)));
+        found.dedup_by(|a, b| a.1 == b.1);
+        found.into_iter().take(limit).map(|(_, w)| w).collect()
+    }
+    /// Search the definitions of all dictionaries for all words of `query`, at most `limit`.
+    /// each hit has the headword and a snippet around the words, preferring them in order.
+    pub fn search_text(&self, query: &[u8], limit: usize) -> Vec<dictionary::TextHit<'_>> {
+        let tokens: Vec<String> = fulltext::tokenize(&String::from_utf8_lossy(query))
+            .into_iter()
+            .map(|(_, t)| t)
+            .collect();
+        let mut found = Vec::new();
+        if tokens.is_empty() {
+            return found;
+        }
+        for d in self.directories.iter() {
+            d.search_text(&tokens, limit, &mut found);
+        }
+        found
+    }
+    /// Lookup the word. Find all case-insensitive matching words in the Idx and Syn,
+    /// the exact matches of each dictionary first. each result has its `MatchKind`, and
+    /// the synonym which matched if it is found by Syn.
+    /// A dictionary without any match tries the normalized word, ignoring accents and
+    /// the kinds of apostrophes and dashes, these results are marked `approximate`.
+    /// If nothing is found at all, the stems by the Hunspell affix sets(see `load_hunspell()`)
+    /// are looked up in their dictionaries, and the English base forms, like "study" for
+    /// "studied", in the others. the results have the `base_form`.
+    pub fn lookup(
+        &self,
+        word: &[u8],
+    ) -> Result<Vec<dictionary::LookupResult<'_>>, result::DictError> {
+        let mut ret: Vec<dictionary::LookupResult> = Vec::with_capacity(self.directories.len());
+        for d in self.directories.iter() {
+            if let Ok(x) = d.lookup(word) {
+                ret.extend(x);
+            }
+        }
+        if !ret.is_empty() {
+            return Ok(ret);
+        }
+        // try the stems by the affix set of each group, the first one found is used.
+        let text = String::from_utf8_lossy(word);
+        for (affix, _, members) in self.affixes.iter() {
+            for stem in affix.stems(&text) {
+                let n = ret.len();
+                for i in members.iter() {
+                    if let Ok(x) = self.directories[*i].lookup(stem.as_bytes()) {
+                        ret.extend(x);
+                    }
+                }
+                if ret.len() > n {
+                    for r in ret[n..].iter_mut() {
+                        r.base_form = Some(stem.clone().into_bytes());
+                    }
+                    break;
+                }
+            }
+        }
+        // try the base forms of an English word in the other dictionaries.
+        if ret.is_empty() {
+            for base in lemma::base_forms(word) {
+                for (i, d) in self.directories.iter().enumerate() {
+                    if self.affixes.iter().any(|(_, _, m)| m.contains(&i)) {
+                        continue;
+                    }
+                    if let Ok(x) = d.lookup(&base) {
+                        ret.extend(x);
+                    }
+                }
+                if !ret.is_empty() {
+                    for r in ret.iter_mut() {
+                        r.base_form = Some(base.clone());
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge<'a>(lists: &[&[&'a str]]) -> Vec<&'a str> {
+        let mut wordit: Vec<_> = lists
+            .iter()
+            .map(|l| l.iter().map(|w| w.as_bytes()))
+            .collect();
+        let cur = wordit.iter_mut().map(|it| it.next()).collect();
+        WordMergeIter { wordit, cur }
+            .map(|w| std::str::from_utf8(w).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn merge_dedups_case_variants() {
+        assert_eq!(
+            merge(&[&["a", "c", "e"], &["b", "c", "d"]]),
+            vec!["a", "b", "c", "d", "e"]
+        );
+        // the neighbors of "москва" start at the case variant found in each dictionary.
+        assert_eq!(
+            merge(&[&["Москва", "Мост"], &["москва", "москвич"]]),
+            vec!["Москва", "Мост", "москвич"]
+        );
+        assert_eq!(
+            merge(&[&["polish", "pot"], &["Polish", "pot"], &[]]),
+            vec!["Polish", "pot"]
+        );
+    }
+}
@@ -1,151 +1,20 @@
-//extern crate regex;
-
-pub mod dict;
-pub mod dictionary;
-pub mod idx;
-pub mod ifo;
-pub mod reformat;
-pub mod result;
-pub mod syn;
-//pub mod web;
+mod api;
+mod cli;
+mod json;
+mod reformat;
+mod web;
 
 use regex::bytes::Regex;
-use std::cmp::Ordering;
+use stardict::{Entry, StarDict};
 use std::io::prelude::*;
-use std::iter::Iterator;
-use std::mem;
 use std::net::TcpListener;
 use std::net::TcpStream;
-use std::{env, fs, path, str};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::{env, fs, path, process, str};
 //use self::regex::Error;
 
-/// StarDict contains all dictionary found within the specified file system directory.
-pub struct StarDict {
-    directories: Vec<dictionary::Dictionary>,
-}
-
-/// An iterator that merges several underlying iterators. try to dedup one duplicated
-/// word from each iterator.
-pub struct WordMergeIter<T: Iterator<Item = Vec<u8>>> {
-    wordit: Vec<T>,
-    cur: Vec<Option<Vec<u8>>>,
-}
-impl<'a, T: Iterator<Item = Vec<u8>>> Iterator for WordMergeIter<T> {
-    type Item = Vec<u8>;
-    fn next(&mut self) -> Option<Self::Item> {
-        let l = self.cur.len();
-        if l == 0 {
-            return None;
-        }
-
-        let mut x = 0usize;
-        let mut i = 1usize;
-        while i < l {
-            x = match (&self.cur[x], &self.cur[i]) {
-                (None, _) => i,
-                (_, None) => x,
-                (Some(a), Some(b)) => match idx::Idx::dict_cmp(&a, &b, false) {
-                    Ordering::Greater => i,
-                    Ordering::Equal => {
-                        self.cur[i] = self.wordit[i].next();
-                        x
-                    }
-                    _ => x,
-                },
-            };
-            i += 1;
-        }
-        mem::replace(&mut self.cur[x], self.wordit[x].next())
-    }
-}
-
-impl StarDict {
-    /// Create a StarDict struct from a system path. in the path,
-    /// there should be some directories. each directory contains
-    /// the dict files, like .ifo, .idx, .dict, etc.
-    /// The dictionary will be sorted by its directory name.
-    pub fn new(root: &path::Path) -> Result<StarDict, result::DictError> {
-        let mut sort_dirs = Vec::new();
-        let mut items = Vec::new();
-
-        if root.is_dir() {
-            for it in fs::read_dir(root)? {
-                //println!("push direc: {:?}", it);
-                let it = it?.path();
-                if it.is_dir() {
-                    sort_dirs.push(it.into_boxed_path());
-                }
-            }
-        }
-
-        sort_dirs.sort();
-        for it in sort_dirs.iter() {
-            match dictionary::Dictionary::new(&**it, root) {
-                Ok(d) => {
-                    items.push(d);
-                }
-                Err(e) => {
-                    eprintln!("ignore reason: {:?}", e);
-                }
-            }
-        }
-        Ok(StarDict { directories: items })
-    }
-    /// Get the Ifo struct, which is parsed from the .ifo file.
-    pub fn info(&self) -> Vec<&ifo::Ifo> {
-        let mut items = Vec::with_capacity(self.directories.len());
-        for it in &self.directories {
-            items.push(&it.ifo);
-        }
-        items
-    }
-    /// List the following neighbor words of `word`, from `off`.
-    /// If `off` is a negative number, list from before `-off`.
-    pub fn neighbors(&self, word: &[u8], off: i32) -> WordMergeIter<dictionary::DictNeighborIter> {
-        let mut wordit = Vec::with_capacity(2 * self.directories.len());
-        let mut cur = Vec::with_capacity(2 * self.directories.len());
-        for d in self.directories.iter() {
-            let mut x = d.neighbors(word, off);
-            let mut s = d.neighbors_syn(word, off);
-            cur.push(x.next());
-            cur.push(s.next());
-            wordit.push(x);
-            wordit.push(s);
-        }
-
-        WordMergeIter { wordit, cur }
-    }
-    /// Search from all dictionaries. using the specified regular expression.
-    /// to match the beginning of a word, use `^`, the ending of a word, use `$`.
-    pub fn search<'a>(&'a self, reg: &'a Regex) -> WordMergeIter<dictionary::IdxIter> {
-        let mut wordit = Vec::with_capacity(2 * self.directories.len());
-        let mut cur = Vec::with_capacity(2 * self.directories.len());
-        for d in self.directories.iter() {
-            //println!("in for {}", d.ifo.name.as_str());
-            let mut x = d.search_regex(reg);
-            let mut s = d.search_syn(reg);
-            //println!("created inner iter");
-            cur.push(x.next());
-            cur.push(s.next());
-            //println!("created 1st value");
-            wordit.push(x);
-            wordit.push(s);
-        }
-
-        WordMergeIter { wordit, cur }
-    }
-    /// Lookup the word. Find in the Idx case-sensitively, if not found then try to do
-    /// case-insensitive search. Also find all case-insensitive matching words in Syn.
-    pub fn lookup(&self, word: &[u8]) -> Result<Vec<dictionary::LookupResult>, result::DictError> {
-        let mut ret: Vec<dictionary::LookupResult> = Vec::with_capacity(self.directories.len());
-        for d in self.directories.iter() {
-            if let Ok(x) = d.lookup(word) {
-                ret.extend(x);
-            }
-        }
-        Ok(ret)
-    }
-}
 struct StardictUrl {
     path: [u8; 4usize],
     word: Vec<u8>,
@@ -188,15 +57,31 @@ fn main() {
     let mut host = String::from("0.0.0.0:8888");
     //let mut host = String::from("[::]:8888");
     let mut dictdir = String::from("/usr/share/stardict/dic");
-    let dict;
+    let mut threads = 4usize;
+    let mut queue = 16usize;
+    // command line mode, like `lookup WORD`.
+    let mut command: Vec<String> = Vec::new();
+    let mut format = cli::Format::Text;
+    let mut offset = 0i32;
+    let mut length = 10usize;
+    // max edit distance of the suggestions.
+    let mut max_dist = 2usize;
+    // the directory of hunspell.conf and the affix files.
+    let mut affixdir = String::new();
+    // seconds between the scans of the dictionary root for changes, 0 to never scan.
+    let mut reload = 60usize;
+    // read the .idx and .syn files into memory instead of mapping them.
+    let mut in_memory = false;
+    let mut dict;
     {
         let mut _daemon = false;
         let mut pendarg = 0u8;
 
         for arg in env::args().skip(1) {
             //parse options.
-            println!("cmd args: {}", &arg);
             let a = arg.as_bytes();
+            // -d takes no value.
+            let is_value = pendarg != 0 && pendarg != b'd';
             match pendarg {
                 b'h' => {
                     host.clear();
@@ -212,44 +97,132 @@ fn main() {
                     dictdir.push_str(&arg);
                     pendarg = 0;
                 }
+                b'a' => {
+                    affixdir.clear();
+                    affixdir.push_str(&arg);
+                    pendarg = 0;
+                }
+                b't' | b'q' | b'l' | b'e' | b'u' if arg.parse::<usize>().is_ok() => {
+                    let v = arg.parse().unwrap();
+                    match pendarg {
+                        b't' => threads = v,
+                        b'q' => queue = v,
+                        b'e' => max_dist = v,
+                        b'u' => reload = v,
+                        _ => length = v,
+                    }
+                    pendarg = 0;
+                }
+                b'o' if arg.parse::<i32>().is_ok() => {
+                    offset = arg.parse().unwrap();
+                    pendarg = 0;
+                }
+                b'f' if cli::Format::from_arg(&arg).is_some() => {
+                    format = cli::Format::from_arg(&arg).unwrap();
+                    pendarg = 0;
+                }
                 0 => (),
                 _ => {
-                    println!("parameter: [-d] [-h host:port] [-r dict-root-dir]");
+                    println!("parameter: [-d] [-h host:port] [-r dict-root-dir] [-a affix-dir] [-t threads] [-q queue-length] [-e max-edit-distance] [-u reload-seconds] [-m]");
+                    println!("       or: [-r dict-root-dir] [-a affix-dir] [-f text|color|json] [-o offset] [-l length] [-e max-edit-distance] lookup WORD|search REGEX|glob PATTERN|text WORDS|neighbors WORD|suggest WORD");
                     return;
                 }
             }
-            if a[0] == b'-' {
-                pendarg = a[1];
+            if !is_value {
+                if a == b"-m" {
+                    in_memory = true;
+                } else if a.len() > 1 && a[0] == b'-' {
+                    pendarg = a[1];
+                } else {
+                    command.push(arg);
+                }
+            }
+        }
+        // the command line mode writes only its results.
+        if command.is_empty() {
+            for arg in env::args().skip(1) {
+                eprintln!("cmd args: {}", &arg);
             }
         }
         //println!("get arg host={}, daemon={}", host, daemon);
         //if daemon {
         //}
 
-        dict = StarDict::new(&path::PathBuf::from(&dictdir)).unwrap();
+        dict = StarDict::open(&path::PathBuf::from(&dictdir), !in_memory).unwrap();
+        let affixp = if affixdir.is_empty() {
+            path::PathBuf::from(&dictdir).join("hunspell")
+        } else {
+            path::PathBuf::from(&affixdir)
+        };
+        if affixp.join("hunspell.conf").is_file() {
+            if let Err(e) = dict.load_hunspell(&affixp) {
+                eprintln!("ignore hunspell: {}", e);
+            }
+        }
     }
-    println!("dict size={}", dict.directories.len());
-    //for d in dict.info().iter() {
-    //    println!("dict: wordcount:{} {}", d.word_count, d.name);
-    //}
-    //webs
-    let listener = TcpListener::bind(&host).expect("Bind Socket failed!");
-    //let pool = web::ThreadPool::new(4);
     let cr = {
         let mut fmtp = path::PathBuf::from(&dictdir);
         fmtp.push("rformat.conf");
         reformat::ContentReformat::from_config_file(&fmtp)
     };
+    if !command.is_empty() {
+        process::exit(cli::run(
+            &dict, &cr, &command, format, offset, length, max_dist,
+        ));
+    }
+    println!("dict size={}", dict.info().len());
+    //for d in dict.info().iter() {
+    //    println!("dict: wordcount:{} {}", d.word_count, d.name);
+    //}
+    //webs
+    let listener = TcpListener::bind(&host).expect("Bind Socket failed!");
+    let dict = Arc::new(web::Shared::new(dict));
+    {
+        // the indexes are built in the background, not by the first request needing each.
+        let dict = dict.get();
+        thread::spawn(move || dict.prepare());
+    }
+    if reload > 0 {
+        // the changed dictionaries are opened again in the background, then swapped in
+        // with their indexes built.
+        let dict = Arc::clone(&dict);
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(reload as u64));
+            let old = dict.get();
+            match old.refresh() {
+                Ok(Some(d)) => {
+                    for f in d.loaded(&old) {
+                        println!("load {}", f.display());
+                    }
+                    println!("dict size={}", d.info().len());
+                    d.prepare();
+                    dict.set(d);
+                }
+                Ok(None) => (),
+                Err(e) => eprintln!("reload failed: {:?}", e),
+            }
+        });
+    }
+    // dict, cr and dictdir are owned by the handler, and shared by all workers.
+    // each request uses the dictionaries current when it begins.
+    let pool = web::ThreadPool::new(threads, queue, move |stream: TcpStream| {
+        if handle_connection(stream, &dict.get(), &cr, &dictdir, max_dist).is_err() {
+            println!("communication failed!");
+        }
+    });
 
     for stream in listener.incoming() {
         let stream = stream.expect("accept TCP failed!");
+        // a slow client must not hold a worker forever.
+        stream.set_read_timeout(Some(Duration::from_secs(10))).ok();
+        stream.set_write_timeout(Some(Duration::from_secs(10))).ok();
 
-        //pool.execute(
-        if let Err(_) = handle_connection(stream, &dict, &cr, &dictdir) {
-            println!("communication failed!");
+        if let Err(mut stream) = pool.execute(stream) {
+            // all workers are busy and the queue is full.
+            stream
+                .write_all(b"HTTP/1.0 503 Service Unavailable\r\n\r\nBusy")
+                .ok();
         }
-
-        //);
     }
 
     println!("Shutting down.");
@@ -259,6 +232,7 @@ fn handle_connection(
     dict: &StarDict,
     cr: &reformat::ContentReformat,
     dictdir: &str,
+    max_dist: usize,
 ) -> std::io::Result<()> {
     //stream.set_nonblocking(false)?;
     //stream.set_nodelay(false)?;
@@ -270,7 +244,7 @@ fn handle_connection(
             sz += bn;
 
             if bn == 0 || sz <= 4 || sz > 4096 {
-                stream.write(b"HTTP/1.0 417 Expectation Failed\r\n\r\nFail")?;
+                stream.write_all(b"HTTP/1.0 417 Expectation Failed\r\n\r\nFail")?;
                 return Ok(());
             }
 
@@ -293,6 +267,8 @@ fn handle_connection(
     //("HTTP/1.0 200 OK\r\nConnection: close\r\n", "index.html");
     let mut content: Vec<u8> = Vec::new();
     let mut surl = StardictUrl::new();
+    // the MIME type of a binary field, by `/b/`.
+    let mut blob_type = "";
 
     if buffer.starts_with(get) {
         let mut state = 0i16; //>=0 path, -1 w, -2 p0w, -3 p1w, -4 argKey, -5 argVal
@@ -326,12 +302,8 @@ fn handle_connection(
                                 b'&' => {
                                     state = -4;
                                 }
-                                b'-' => {
-                                    if w == b'o' {
-                                        w = b'O';
-                                    } else {
-                                        state = -32768;
-                                    }
+                                b'-' if w == b'o' => {
+                                    w = b'O';
                                 }
                                 b'0'..=b'9' => {
                                     let v: i32 = (*c - b'0') as i32;
@@ -365,19 +337,31 @@ fn handle_connection(
         if surl.length == 0 {
             surl.length = 10;
         }
-        if surl.word.len() > 0 {
+        if !surl.word.is_empty() {
             if surl.path[0] == b'W' {
                 //word lookup
                 match dict.lookup(&surl.word) {
                     Ok(x) => {
+                        if let Some(b) = x.first().and_then(|e| e.base_form.as_ref()) {
+                            // like "studied → study".
+                            content.extend(b"<div class='res_word'>");
+                            // both come from the request, not from a dictionary.
+                            html_escape(&surl.word, &mut content);
+                            content.extend(" → ".as_bytes());
+                            html_escape(b, &mut content);
+                            content.extend(b"</div>");
+                        }
                         content.extend(b"<ol>");
                         for (i, e) in x.iter().enumerate() {
                             content.extend(b"<li><a href='#word_");
                             content.extend(i.to_string().as_bytes());
                             content.extend(b"'>");
-                            content.extend(&e.word);
+                            see_word(e, &mut content);
                             content.extend(b" : ");
                             content.extend(e.dictionary.name.as_bytes());
+                            if e.approximate {
+                                content.extend(b" (approximate)");
+                            }
                             content.extend(b"</a></li>");
                         }
                         content.extend(b"</ol>\n");
@@ -388,36 +372,58 @@ fn handle_connection(
                             content.extend(b"' class='res_word'>");
                             content.extend(e.dictionary.name.as_bytes());
                             content.extend(b" (");
-                            content.extend(&e.word);
-                            content.extend(b") </div><div class='res_definition'>".iter());
-                            for (a, b) in e
-                                .dictionary
-                                .same_type_sequence
-                                .as_bytes()
-                                .iter()
-                                .zip(e.result.split(|c| *c == 0))
-                            {
-                                content.extend(&cr.replace_all(
-                                    *a,
-                                    e.dictionary.dict_path.as_bytes(),
-                                    b,
-                                ));
+                            see_word(e, &mut content);
+                            content.extend(b") </div>");
+                            if !e.synonyms.is_empty() {
+                                // like "also: colour, colours".
+                                content.extend(b"<div class='res_also'>also: ");
+                                content.extend(e.synonyms.join(&b", "[..]));
+                                content.extend(b"</div>");
                             }
+                            content.extend(b"<div class='res_definition'>".iter());
+                            let path = e.dictionary.dict_path.as_bytes();
+                            content.extend(&cr.render(&e.entry, path, |n, t, out| {
+                                binary_field(e.dictionary.name.as_bytes(), e.index, n, t, out)
+                            }));
                             content.extend(b"</div>\n");
                         }
+                        if x.is_empty() {
+                            content.extend(b"did you mean:");
+                            suggest_list(dict, &surl.word, max_dist, surl.length, &mut content);
+                        }
                     }
                     Err(e) => println!("err: {:?}", e),
                 }
+            } else if surl.path[0] == b'f' {
+                //fuzzy suggestions of a misspelled word
+                suggest_list(dict, &surl.word, max_dist, surl.length, &mut content);
+            } else if surl.path[0] == b't' {
+                //full-text search in definitions
+                content.extend(b"/~/:<ol>");
+                for e in dict.search_text(&surl.word, surl.length) {
+                    content.extend(b"<li><a>");
+                    content.extend(e.word);
+                    content.extend(b"</a> : ");
+                    content.extend(e.dictionary.name.as_bytes());
+                    content.extend(b"<div class='res_definition'>");
+                    html_escape(e.snippet.as_bytes(), &mut content);
+                    content.extend(b"</div></li>\n");
+                }
+                content.extend(b"</ol>");
             } else if surl.path[0] == b'n' {
                 //neighbor words reference
                 for s in dict.neighbors(&surl.word, surl.offset).take(surl.length) {
                     content.extend(s);
                     content.extend(b"\n");
                 }
-            } else if surl.path[0] == b's' {
-                //search with regex
+            } else if surl.path[0] == b's' || surl.path[0] == b'g' {
+                //search with regex, or glob pattern like `ab*c?`
                 match str::from_utf8(&surl.word) {
-                    Ok(x) => match Regex::new(x) {
+                    Ok(x) => match if surl.path[0] == b's' {
+                        Regex::new(x)
+                    } else {
+                        stardict::glob_regex(x)
+                    } {
                         Ok(v) => {
                             content.extend(b"/~/:<ol>");
                             dict.search(&v).take(surl.length).for_each(|e| {
@@ -431,17 +437,49 @@ fn handle_connection(
                     },
                     Err(e) => println!("err: {:?}", e),
                 }
+            } else if surl.path[0] == b'b' {
+                //binary field of an entry, like `/b/INDEX/FIELD/BOOKNAME`
+                let mut it = surl.word.splitn(3, |c| *c == b'/');
+                let index = it.next().and_then(|x| str::from_utf8(x).ok()?.parse().ok());
+                let field = it.next().and_then(|x| str::from_utf8(x).ok()?.parse().ok());
+                let name = it.next().and_then(|x| str::from_utf8(x).ok());
+                if let (Some(index), Some(field), Some(name)) = (index, field, name) {
+                    match dict.field(name, index, field) {
+                        Ok((t, data)) if Entry::is_binary(t) => {
+                            blob_type = Entry::mime_type(t, &data);
+                            content = data;
+                        }
+                        Ok(_) => (),
+                        Err(e) => println!("err: {:?}", e),
+                    }
+                }
             } else if surl.path[0] == b'r' {
                 //html js css page etc.
                 if let Ok(fname) = str::from_utf8(&surl.word) {
-                    let mut pfile = path::PathBuf::from(dictdir);
-                    pfile.push(fname);
-                    if let Ok(mut f) = fs::File::open(pfile) {
-                        if f.read_to_end(&mut content).is_err() {
-                            content.clear();
+                    // the resource database of the dictionary first, then the file.
+                    if let Some(data) = dict.resource(fname) {
+                        content = data;
+                    } else {
+                        let mut pfile = path::PathBuf::from(dictdir);
+                        pfile.push(fname);
+                        if let Ok(mut f) = fs::File::open(pfile) {
+                            if f.read_to_end(&mut content).is_err() {
+                                content.clear();
+                            }
                         }
                     }
                 }
+            } else if surl.path[0] == b'a' {
+                //structured data in JSON
+                api::handle(
+                    dict,
+                    cr,
+                    &surl.word,
+                    surl.offset,
+                    surl.length,
+                    max_dist,
+                    &mut content,
+                );
             } else if surl.path[0] == b'w' {
                 content.extend(HOME_PAGE.as_bytes());
             }
@@ -450,6 +488,70 @@ fn handle_connection(
         }
     }
 
+    fn suggest_list(
+        dict: &StarDict,
+        word: &[u8],
+        max_dist: usize,
+        length: usize,
+        content: &mut Vec<u8>,
+    ) {
+        content.extend(b"<ol>");
+        for w in dict.suggest(word, max_dist, length) {
+            content.extend(b"<li><a>");
+            content.extend(w);
+            content.extend(b"</a></li>\n");
+        }
+        content.extend(b"</ol>");
+    }
+    /// the word of a result, like "colour → see color" if it is found by a synonym.
+    fn see_word(e: &stardict::LookupResult, content: &mut Vec<u8>) {
+        if let Some(s) = &e.synonym {
+            content.extend(s);
+            content.extend(" → see ".as_bytes());
+        }
+        content.extend(&e.word);
+    }
+    /// reference the binary field `n` of type `t` of the entry at `index`, served by `/b/`.
+    /// a wav is played by `<audio>`, a picture is shown by `<img>`, others are links.
+    fn binary_field(name: &[u8], index: usize, n: usize, t: u8, out: &mut Vec<u8>) {
+        let mut url = format!("/b/{}/{}/", index, n).into_bytes();
+        for c in name {
+            if c.is_ascii_alphanumeric() || b"-_.~".contains(c) {
+                url.push(*c);
+            } else {
+                url.extend(format!("%{:02X}", c).as_bytes());
+            }
+        }
+        match t {
+            b'W' => {
+                out.extend(b"<audio controls src='");
+                out.extend(&url);
+                out.extend(b"'></audio>");
+            }
+            b'P' => {
+                out.extend(b"<img src='");
+                out.extend(&url);
+                out.extend(b"'/>");
+            }
+            _ => {
+                out.extend(b"<a href='");
+                out.extend(&url);
+                out.extend(b"'>[");
+                out.push(t);
+                out.extend(b"]</a>");
+            }
+        }
+    }
+    fn html_escape(text: &[u8], content: &mut Vec<u8>) {
+        for c in text {
+            match c {
+                b'<' => content.extend(b"&lt;"),
+                b'>' => content.extend(b"&gt;"),
+                b'&' => content.extend(b"&amp;"),
+                _ => content.push(*c),
+            }
+        }
+    }
     fn map_by_file(f: &[u8]) -> &'static [u8] {
         if let Some(s) = f.rsplit(|c| *c == b'.').next() {
             match s {
@@ -457,40 +559,49 @@ fn handle_connection(
                 b"css" => return b"text/css",
                 b"jpg" => return b"image/jpeg",
                 b"png" => return b"image/png",
+                b"gif" => return b"image/gif",
+                b"svg" => return b"image/svg+xml",
+                b"wav" => return b"audio/wav",
+                b"mp3" => return b"audio/mpeg",
+                b"ogg" => return b"audio/ogg",
                 _ => (),
             }
         }
         b"text/html"
     }
-    if content.len() > 0 {
+    if !content.is_empty() {
         //let mut cg = 0;
         //content.iter_mut().for_each(|x|{ *x = if cg % 10 == 0 {b'\n'} else {b'a'}; cg = cg + 1;});
-        stream.write(b"HTTP/1.0 200 OK\r\nContent-Type: ")?;
+        stream.write_all(b"HTTP/1.0 200 OK\r\nContent-Type: ")?;
         if surl.path[0] == b'n' {
-            stream.write(b"text/plain")?;
+            stream.write_all(b"text/plain")?;
         } else if surl.path[0] == b'r' {
-            stream.write(map_by_file(&surl.word))?;
+            stream.write_all(map_by_file(&surl.word))?;
+        } else if surl.path[0] == b'a' {
+            stream.write_all(b"application/json; charset=utf-8")?;
+        } else if surl.path[0] == b'b' {
+            stream.write_all(blob_type.as_bytes())?;
         } else {
-            stream.write(b"text/html")?;
+            stream.write_all(b"text/html")?;
         }
-        stream.write(b"\r\nContent-Length: ")?;
-        stream.write(content.len().to_string().as_bytes())?;
-        stream.write(b"\r\nConnection: close\r\n\r\n")?;
-        //stream.write(b"\r\n\r\n")?;
+        stream.write_all(b"\r\nContent-Length: ")?;
+        stream.write_all(content.len().to_string().as_bytes())?;
+        stream.write_all(b"\r\nConnection: close\r\n\r\n")?;
+        //stream.write_all(b"\r\n\r\n")?;
         /*
         for blk in content.chunks(1024) {
-            stream.write(blk)?;
+            stream.write_all(blk)?;
         }
         */
-        stream.write(&content)?;
+        stream.write_all(&content)?;
     } else {
-        stream.write(b"HTTP/1.0 404 NOT FOUND\r\n\r\nnot found")?;
+        stream.write_all(b"HTTP/1.0 404 NOT FOUND\r\n\r\nnot found")?;
     }
     stream.flush()?;
     //stream.shutdown(std::net::Shutdown::Both)?;
     Ok(())
 }
-const HOME_PAGE: &'static str = r"<html><head>
+const HOME_PAGE: &str = r"<html><head>
 <meta http-equiv='Content-Type' content='text/html; charset=UTF-8' />
 <title>Star Dictionary</title>
 <style>
@@ -0,0 +1,93 @@
+use std::cmp::Ordering;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use super::idx::Idx;
+use super::syn::Syn;
+
+/// the normalized key of `word`, to find "café" by "cafe" and "don’t" by "don't".
+/// it is decomposed by NFKD, the combining marks are removed, apostrophes become `'`,
+/// hyphens and dashes become `-`, and it is lower cased.
+pub fn key(word: &[u8]) -> String {
+    String::from_utf8_lossy(word)
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{201b}' | '\u{2032}' | '\u{02bc}' | '\u{00b4}' | '`' => {
+                '\''
+            }
+            '\u{2010}'..='\u{2015}' | '\u{2212}' | '\u{fe58}' | '\u{fe63}' | '\u{ff0d}' => '-',
+            c => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// the normalized keys of all words of a dictionary, from Idx and Syn, sorted by the key.
+pub struct NormTable {
+    keys: Vec<u8>,                 //all keys
+    entries: Vec<(u32, u32, u32)>, //start and end in keys, and the Idx position of each key
+}
+
+impl NormTable {
+    /// build the table from all words of `idx` and `syn`.
+    pub fn new(idx: &Idx, syn: Option<&Syn>) -> NormTable {
+        let mut t = NormTable {
+            keys: Vec::new(),
+            entries: Vec::with_capacity(idx.len() + syn.map_or(0, |s| s.len())),
+        };
+        for i in 0..idx.len() {
+            if let Ok(w) = idx.get_word(i) {
+                t.push(w, i);
+            }
+        }
+        if let Some(s) = syn {
+            for i in 0..s.len() {
+                if let Ok(p) = s.get_offset(i) {
+                    t.push(s.get_word(i).unwrap(), p);
+                }
+            }
+        }
+        let keys = &t.keys;
+        t.entries.sort_unstable_by(|a, b| {
+            keys[a.0 as usize..a.1 as usize]
+                .cmp(&keys[b.0 as usize..b.1 as usize])
+                .then(a.2.cmp(&b.2))
+        });
+        t
+    }
+    fn push(&mut self, word: &[u8], pos: usize) {
+        let start = self.keys.len() as u32;
+        self.keys.extend(key(word).as_bytes());
+        self.entries
+            .push((start, self.keys.len() as u32, pos as u32));
+    }
+    fn key_at(&self, i: usize) -> &[u8] {
+        let (s, e, _) = self.entries[i];
+        &self.keys[s as usize..e as usize]
+    }
+    /// the Idx positions of the words with the same normalized key as `word`.
+    pub fn get(&self, word: &[u8]) -> Vec<usize> {
+        let k = key(word);
+        let k = k.as_bytes();
+        let (mut lo, mut hi) = (0usize, self.entries.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.key_at(mid).cmp(k) == Ordering::Less {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let mut ret = Vec::new();
+        while lo < self.entries.len() && self.key_at(lo) == k {
+            let p = self.entries[lo].2 as usize;
+            if !ret.contains(&p) {
+                ret.push(p);
+            }
+            lo += 1;
+        }
+        ret
+    }
+}
@@ -9,6 +9,8 @@ use std::{fs, io, path};
 use aho_corasick::AhoCorasick;
 use regex::bytes::{NoExpand, Regex};
 
+use stardict::Entry;
+
 /// Used to replace strings in the lookup result.
 /// see ContentReformat.
 pub struct Replacer {
@@ -44,32 +46,22 @@ impl ContentReformat {
     ///   be replaced with dictionary path.
     ///5. Regular expression replace: x~y replaces any text that matches x, with y as Regex replacement string.
     pub fn from_config_file(config: &path::Path) -> ContentReformat {
-        let file;
-        match fs::File::open(config) {
-            Ok(f) => file = f,
+        let file = match fs::File::open(config) {
+            Ok(f) => f,
             Err(e) => {
-                println!("open config failed:{:?}", e);
+                eprintln!("open config failed:{:?}", e);
                 return ContentReformat {
                     repl: HashMap::new(),
                     regex_cache: HashMap::new(),
                 };
             }
-        }
+        };
         let mut repl: HashMap<u8, Vec<Replacer>> = HashMap::new();
         let mut dict_format = 0u8;
         let mut regex_cache = HashMap::new();
         io::BufReader::new(file)
             .split(b'\n')
-            .filter(|x| match x {
-                Ok(v) => {
-                    if v.len() > 0 && v[0] != b'#' {
-                        true
-                    } else {
-                        false
-                    }
-                }
-                _ => false,
-            })
+            .filter(|x| matches!(x, Ok(v) if !v.is_empty() && v[0] != b'#'))
             .for_each(|x| {
                 if let Ok(v) = x {
                     if v.len() > 1 && v[0] == b':' {
@@ -145,6 +137,23 @@ impl ContentReformat {
             });
         ContentReformat { repl, regex_cache }
     }
+    /// reformat all text fields of `entry` by `replace_all()`, joined together.
+    /// each binary field is written by `binary` with its field number and type instead,
+    /// like a reference to the picture.
+    pub fn render<F>(&self, entry: &Entry, dict_path: &[u8], mut binary: F) -> Vec<u8>
+    where
+        F: FnMut(usize, u8, &mut Vec<u8>),
+    {
+        let mut ret = Vec::new();
+        for (n, (t, f)) in entry.fields.iter().enumerate() {
+            if Entry::is_binary(*t) {
+                binary(n, *t, &mut ret);
+            } else {
+                ret.extend(self.replace_all(*t, dict_path, f));
+            }
+        }
+        ret
+    }
     /// find all text in `haystack`, according to `dict_format` and `dict_path`, to
     /// the replacement in `self`, using `AhoCorasick` to make the text replacement.
     pub fn replace_all(&self, dict_format: u8, dict_path: &[u8], haystack: &[u8]) -> Vec<u8> {
@@ -166,11 +175,10 @@ impl ContentReformat {
 
                     for s in v.line[(v.op_idx + 1)..].split(|x| *x == b'@') {
                         if not_first {
-                            if s.len() > 0 {
-                                match s[0] {
-                                    b'p' => bufe.extend(dict_path),
-                                    // add other variables.
-                                    _ => (),
+                            if !s.is_empty() {
+                                // add other variables.
+                                if s[0] == b'p' {
+                                    bufe.extend(dict_path);
                                 }
                                 //println!("dict path={} p={} {}", std::str::from_utf8(dict_path).unwrap(), s[0], b'p');
                                 bufe.extend(&s[1..]);
@@ -193,14 +201,18 @@ impl ContentReformat {
                     };
                     */
                     if let Some(re) = self.regex_cache.get(&(dict_format, hi)) {
-                        match re.replace_all(&hay, NoExpand(&v.line[(v.op_idx + 1)..])) {
-                            Cow::Owned(o) => hay = Cow::Owned(o),
-                            _ => (),
+                        if let Cow::Owned(o) =
+                            re.replace_all(&hay, NoExpand(&v.line[(v.op_idx + 1)..]))
+                        {
+                            hay = Cow::Owned(o)
                         }
                     }
                 }
             }
         }
-        AhoCorasick::new(&from).replace_all_bytes(&hay, &to)
+        match AhoCorasick::new(&from) {
+            Ok(ac) => ac.replace_all_bytes(&hay, &to),
+            Err(_) => hay.into_owned(),
+        }
     }
 }
@@ -0,0 +1,170 @@
+use std::io::BufRead;
+use std::{fs, io, path};
+
+use super::dict::Dict;
+use super::idx::IdxData;
+use super::result::DictError;
+
+/// The resource database of a dictionary, the pictures and sounds its definitions refer to.
+/// it is made of 3 files in the directory of the dictionary:
+/// 1. res.rifo: "StarDict's storage ifo file", with `filecount` and `idxoffsetbits`.
+/// 2. res.ridx: sorted by file name, each is a '\0' ended file name, then the offset
+///    u32 or u64(when idxoffsetbits=64) and the size u32 in res.rdic.
+/// 3. res.rdic or res.rdic.dz: the content of the files.
+pub struct Resource {
+    data: IdxData,        //content of res.ridx
+    index: Vec<u32>,      //end of each file name
+    off_len_bytes: usize, //total bytes used for offset and size
+    rdic: Dict,
+}
+
+impl Resource {
+    /// open the resource database of `dir`. Err if there is no res.rifo.
+    /// res.ridx is memory mapped if `mapped`.
+    pub fn open(dir: &path::Path, mapped: bool) -> Result<Resource, DictError> {
+        let mut count = 0usize;
+        let mut bits = 32usize;
+        let mut lines = io::BufReader::new(fs::File::open(dir.join("res.rifo"))?).lines();
+        if lines.next().transpose()?.as_deref() != Some("StarDict's storage ifo file") {
+            return Err(DictError::My("not a res.rifo file".to_string()));
+        }
+        for line in lines {
+            let line = line?;
+            match line.split_once('=') {
+                Some(("filecount", v)) => count = v.trim().parse()?,
+                Some(("idxoffsetbits", v)) => bits = v.trim().parse()?,
+                _ => (),
+            }
+        }
+        if bits != 32 && bits != 64 {
+            return Err(DictError::My(format!("unsupported idxoffsetbits {}", bits)));
+        }
+        let (data, _) = IdxData::open(&dir.join("res.ridx"), mapped)?;
+        let off_len_bytes = bits / 8 + 4;
+        let mut index = Vec::with_capacity(count);
+        let mut pos = 0usize;
+        while pos < data.len() {
+            let end = match data[pos..].iter().position(|c| *c == 0) {
+                Some(n) => pos + n,
+                None => break,
+            };
+            index.push(end as u32);
+            pos = end + 1 + off_len_bytes;
+        }
+        if index.len() != count || pos != data.len() {
+            return Err(DictError::My(format!(
+                "filecount not equal! {} != {}",
+                count,
+                index.len()
+            )));
+        }
+        let rdic = dir.join("res.rdic");
+        let rdic = if rdic.is_file() {
+            Dict::open(&rdic)?
+        } else {
+            Dict::open(&dir.join("res.rdic.dz"))?
+        };
+        Ok(Resource {
+            data,
+            index,
+            off_len_bytes,
+            rdic,
+        })
+    }
+    fn name(&self, i: usize) -> &[u8] {
+        let start = if i == 0 {
+            0
+        } else {
+            self.index[i - 1] as usize + 1 + self.off_len_bytes
+        };
+        &self.data[start..self.index[i] as usize]
+    }
+    /// read the file `name`, like "img/cat.png". the names are compared as bytes.
+    pub fn get(&self, name: &[u8]) -> Result<Vec<u8>, DictError> {
+        let (mut lo, mut hi) = (0usize, self.index.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.name(mid) < name {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        if lo == self.index.len() || self.name(lo) != name {
+            return Err(DictError::NotFound(lo));
+        }
+        let i = lo;
+        let p = self.index[i] as usize + 1;
+        let b = &self.data[p..p + self.off_len_bytes];
+        let (offset, b) = if self.off_len_bytes == 12 {
+            let mut o = [0u8; 8];
+            o.copy_from_slice(&b[..8]);
+            (u64::from_be_bytes(o), &b[8..])
+        } else {
+            (u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as u64, &b[4..])
+        };
+        let size = u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+        self.rdic.read(offset, size as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // write a resource database of `files` into a new directory, with offsets of `bits`.
+    fn write_db(bits: usize, files: &[(&str, &[u8])]) -> path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("stardict-res-{}-{}", std::process::id(), bits));
+        fs::create_dir_all(&dir).unwrap();
+        let mut ridx = Vec::new();
+        let mut rdic = Vec::new();
+        for (name, content) in files {
+            ridx.extend(name.as_bytes());
+            ridx.push(0);
+            if bits == 64 {
+                ridx.extend((rdic.len() as u64).to_be_bytes());
+            } else {
+                ridx.extend((rdic.len() as u32).to_be_bytes());
+            }
+            ridx.extend((content.len() as u32).to_be_bytes());
+            rdic.extend(*content);
+        }
+        let rifo = format!(
+            "StarDict's storage ifo file\nversion=3.0.0\nfilecount={}\nidxoffsetbits={}\n",
+            files.len(),
+            bits
+        );
+        fs::write(dir.join("res.rifo"), rifo).unwrap();
+        fs::write(dir.join("res.ridx"), ridx).unwrap();
+        fs::write(dir.join("res.rdic"), rdic).unwrap();
+        dir
+    }
+
+    #[test]
+    fn open_and_get() {
+        let files: [(&str, &[u8]); 3] = [("a.png", b"png"), ("b/c.wav", b""), ("d.txt", b"text")];
+        for bits in [32, 64] {
+            let dir = write_db(bits, &files);
+            for mapped in [true, false] {
+                let res = Resource::open(&dir, mapped).unwrap();
+                assert_eq!(res.index.len(), 3);
+                for (name, content) in files.iter() {
+                    assert_eq!(res.get(name.as_bytes()).unwrap(), *content);
+                }
+                assert!(res.get(b"b").is_err());
+                assert!(res.get(b"e.png").is_err());
+            }
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn reject_bad_offset_bits() {
+        for bits in [0, 16] {
+            let dir = write_db(bits, &[("a.png", b"png")]);
+            assert!(Resource::open(&dir, true).is_err());
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
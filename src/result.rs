@@ -1,5 +1,6 @@
-use std::{fmt, io, num, str};
+use std::{error, fmt, io, num, str};
 
+/// the error type of all dictionary operations.
 #[derive(Debug)]
 pub enum DictError {
     Io(io::Error),
@@ -40,3 +41,13 @@ impl fmt::Display for DictError {
         }
     }
 }
+impl error::Error for DictError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            DictError::Io(err) => Some(err),
+            DictError::Utf8(err) => Some(err),
+            DictError::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
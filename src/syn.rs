@@ -1,10 +1,10 @@
-use super::idx::Idx;
+use super::cache::OffsetCache;
+use super::idx::{self, Idx, IdxData};
 use super::result::DictError;
 use std::cmp::Ordering;
-use std::fs::File;
-use std::io::{BufReader, Read};
-use std::os::unix::prelude::FileExt;
+use std::ops::Range;
 use std::path;
+use std::sync::OnceLock;
 
 // the bytes used for offset
 const OFF_BYTES: usize = 4;
@@ -15,20 +15,21 @@ const OFF_BYTES: usize = 4;
 /// method does not return the same value as you've expected.
 #[derive(Debug)]
 pub struct Syn {
-    filedesc: File,  //file descriptor
+    data: IdxData,   //content of .syn file
     index: Vec<u32>, //end of each word
 
     firstword: Vec<u8>,  //first word
     middleword: Vec<u8>, //middle word
     lastword: Vec<u8>,   //last word
-                         //cache:
+    //cache:
+    reverse: OnceLock<Vec<(u32, u32)>>, //(Idx position, Syn position) sorted, see prepare()
 }
 
 enum WordPosition {
-    FirstWordPos,
-    MiddleWordPos,
-    LastWordPos,
-    OtherPos,
+    First,
+    Middle,
+    Last,
+    Other,
 }
 enum ParseState {
     Word(WordPosition),
@@ -53,9 +54,9 @@ impl Parser {
                     self.state = ParseState::OffsetLength(0);
                 } else {
                     match pos {
-                        WordPosition::FirstWordPos => self.firstw.push(x),
-                        WordPosition::MiddleWordPos => self.middlew.push(x),
-                        WordPosition::LastWordPos => self.lastw.push(x),
+                        WordPosition::First => self.firstw.push(x),
+                        WordPosition::Middle => self.middlew.push(x),
+                        WordPosition::Last => self.lastw.push(x),
                         _ => (),
                     }
                 }
@@ -65,11 +66,11 @@ impl Parser {
                     ParseState::OffsetLength(*n + 1)
                 } else {
                     if self.result.len() == self.count_m1 {
-                        ParseState::Word(WordPosition::LastWordPos)
+                        ParseState::Word(WordPosition::Last)
                     } else if self.result.len() == self.count_half {
-                        ParseState::Word(WordPosition::MiddleWordPos)
+                        ParseState::Word(WordPosition::Middle)
                     } else {
-                        ParseState::Word(WordPosition::OtherPos)
+                        ParseState::Word(WordPosition::Other)
                     }
                 };
             }
@@ -80,36 +81,51 @@ impl Parser {
 impl Syn {
     ///create Syn struct from file. with count as synword count from .ifo
     ///file. if the count is not correct, return Err(DictError).
-    pub fn open(file: &path::Path, count: usize) -> Result<Syn, DictError> {
-        let mut con = Parser {
-            state: ParseState::Word(WordPosition::FirstWordPos),
-            count_m1: count - 1,
-            count_half: count / 2,
-            off_word: 0,
-            result: Vec::with_capacity(count),
-            firstw: Vec::new(),
-            middlew: Vec::new(),
-            lastw: Vec::new(),
-        };
-        {
-            let syn_file = File::open(file)?;
-            let buf_rd = BufReader::new(syn_file);
-            buf_rd.bytes().for_each(|x| con.parse(x.unwrap()));
-        }
+    ///if the .syn file does not exist, .syn.gz is used. it is memory mapped if `mapped`.
+    pub fn open(file: &path::Path, count: usize, mapped: bool) -> Result<Syn, DictError> {
+        let (data, file) = IdxData::open(file, mapped)?;
+        let file = file.as_path();
+        let oft = match OffsetCache::load(file, count, OFF_BYTES, data.len()) {
+            Ok(c) => c,
+            Err(_) => {
+                let mut con = Parser {
+                    state: ParseState::Word(WordPosition::First),
+                    count_m1: count - 1,
+                    count_half: count / 2,
+                    off_word: 0,
+                    result: Vec::with_capacity(count),
+                    firstw: Vec::new(),
+                    middlew: Vec::new(),
+                    lastw: Vec::new(),
+                };
+                data.iter().for_each(|x| con.parse(*x));
 
-        if count != con.result.len() {
-            return Err(DictError::My(format!(
-                "not equal! {} != {}",
-                count,
-                con.result.len()
-            )));
-        }
+                if count != con.result.len() {
+                    return Err(DictError::My(format!(
+                        "not equal! {} != {}",
+                        count,
+                        con.result.len()
+                    )));
+                }
+                let c = OffsetCache {
+                    index: con.result,
+                    firstword: con.firstw,
+                    middleword: con.middlew,
+                    lastword: con.lastw,
+                };
+                if let Err(e) = c.save(file, OFF_BYTES) {
+                    eprintln!("offset cache not saved for {}: {}", file.display(), e);
+                }
+                c
+            }
+        };
         Ok(Syn {
-            filedesc: File::open(file)?,
-            index: con.result,
-            firstword: con.firstw,
-            middleword: con.middlew,
-            lastword: con.lastw,
+            data,
+            index: oft.index,
+            firstword: oft.firstword,
+            middleword: oft.middleword,
+            lastword: oft.lastword,
+            reverse: OnceLock::new(),
         })
     }
     /// return syn word count.
@@ -117,28 +133,25 @@ impl Syn {
         self.index.len()
     }
     /// return the word in the exact posision. Err(DictError) if not found.
-    pub fn get_word(&self, i: usize) -> Result<Vec<u8>, DictError> {
+    pub fn get_word(&self, i: usize) -> Result<&[u8], DictError> {
         //check range first
         if i >= self.index.len() {
             return Err(DictError::NotFound(i));
         }
 
         if i == 0 {
-            return Ok(self.firstword.clone());
+            return Ok(&self.firstword);
         } else if i == self.index.len() - 1 {
-            return Ok(self.lastword.clone());
+            return Ok(&self.lastword);
         } else if i == self.index.len() / 2 {
-            return Ok(self.middleword.clone());
+            return Ok(&self.middleword);
         }
 
         // no i==0 case here.
         let start = self.index[i - 1] as usize + OFF_BYTES + 1;
         let end = self.index[i] as usize;
         //get data of [start, end)
-        let mut word_result = vec![0u8; end - start];
-        self.filedesc
-            .read_exact_at(&mut word_result, start as u64)?;
-        Ok(word_result)
+        Ok(&self.data[start..end])
     }
     /// return the index of Idx in the exact position. Err(i) if not found.
     pub fn get_offset(&self, i: usize) -> Result<usize, usize> {
@@ -148,23 +161,61 @@ impl Syn {
         }
 
         let start = self.index[i] as usize + 1;
-        let mut buff = [0u8; 4];
-        if let Ok(()) = self.filedesc.read_exact_at(&mut buff, start as u64) {
-            let offset = u32::from_be_bytes(buff);
-            Ok(offset as usize)
-        } else {
-            Err(i)
+        match self.data.get(start..start + OFF_BYTES) {
+            Some(b) => Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize),
+            None => Err(i),
         }
     }
-    /// search the word in Syn case-insensitively. return the index if found,
+    /// get the synonyms pointing to the word at `idx` of Idx, in the order of Syn.
+    pub fn synonyms_of(&self, idx: usize) -> Vec<&[u8]> {
+        let reverse = self.reverse();
+        let start = reverse.partition_point(|(p, _)| (*p as usize) < idx);
+        reverse[start..]
+            .iter()
+            .take_while(|(p, _)| *p as usize == idx)
+            .filter_map(|(_, i)| self.get_word(*i as usize).ok())
+            .collect()
+    }
+    /// build the table from Idx positions to synonyms now, instead of in the first
+    /// `synonyms_of()`.
+    pub fn prepare(&self) {
+        self.reverse();
+    }
+    fn reverse(&self) -> &[(u32, u32)] {
+        self.reverse.get_or_init(|| {
+            let mut r: Vec<(u32, u32)> = (0..self.index.len())
+                .filter_map(|i| self.get_offset(i).ok().map(|p| (p as u32, i as u32)))
+                .collect();
+            r.sort_unstable();
+            r
+        })
+    }
+    /// get the positions of all words beginning with `prefix`, ignoring ASCII case.
+    /// the range is found by binary search, so it is empty if nothing matches.
+    pub fn prefix_range(&self, prefix: &[u8]) -> Range<usize> {
+        idx::prefix_range(self.index.len(), prefix, |i| self.get_word(i).unwrap())
+    }
+    /// search the word in Syn case-insensitively, with Unicode case folding if the
+    /// ASCII case-insensitive search finds nothing. return the index if found,
     /// return Err(usize) if not found. The Err result is used for
     /// neighborhood hint.
     pub fn get(&self, word: &[u8]) -> Result<usize, usize> {
-        if Idx::dict_cmp(&self.get_word(0).unwrap(), word, true) == Ordering::Greater {
+        let ret = self.binary_search(word);
+        if word.is_ascii() {
+            return ret;
+        }
+        ret.or_else(|e| self.get_all(word).first().copied().ok_or(e))
+    }
+    /// get the positions of all words equal to `word` with Unicode case folding,
+    /// see `Idx::fold_cmp`.
+    pub fn get_all(&self, word: &[u8]) -> Vec<usize> {
+        idx::fold_matches(self.index.len(), word, |i| self.get_word(i).unwrap())
+    }
+    fn binary_search(&self, word: &[u8]) -> Result<usize, usize> {
+        if Idx::dict_cmp(self.get_word(0).unwrap(), word, true) == Ordering::Greater {
             return Err(0);
         }
-        if Idx::dict_cmp(&self.get_word(self.index.len() - 1).unwrap(), word, true)
-            == Ordering::Less
+        if Idx::dict_cmp(self.get_word(self.index.len() - 1).unwrap(), word, true) == Ordering::Less
         {
             return Err(self.index.len());
         }
@@ -178,12 +229,12 @@ impl Syn {
             // mid is always in [0, size), that means mid is >= 0 and < size.
             // mid >= 0: by definition
             // mid < size: mid = size / 2 + size / 4 + size / 8 ...
-            let cmp = Idx::dict_cmp(&self.get_word(mid).unwrap(), word, true);
+            let cmp = Idx::dict_cmp(self.get_word(mid).unwrap(), word, true);
             base = if cmp == Ordering::Greater { base } else { mid };
             size -= half;
         }
         // base is always in [0, size) because base <= mid.
-        let cmp = Idx::dict_cmp(&self.get_word(base).unwrap(), word, true);
+        let cmp = Idx::dict_cmp(self.get_word(base).unwrap(), word, true);
         if cmp == Ordering::Equal {
             Ok(base)
         } else {
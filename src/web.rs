@@ -0,0 +1,120 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::thread;
+
+/// A fixed number of worker threads, all running the same handler on the tasks sent to them.
+/// When every worker is busy, tasks wait in a bounded queue. When the queue is full too,
+/// `execute()` gives the task back, so the caller can reject it instead of blocking.
+pub struct ThreadPool<T: Send + 'static> {
+    workers: Vec<Worker>,
+    sender: Option<mpsc::SyncSender<T>>,
+}
+
+struct Worker {
+    id: usize,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> ThreadPool<T> {
+    /// create `size` workers running `handler`, with at most `queue` tasks waiting.
+    /// `size` is at least 1.
+    pub fn new<F>(size: usize, queue: usize, handler: F) -> ThreadPool<T>
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        let size = size.max(1);
+        let (sender, receiver) = mpsc::sync_channel(queue);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let handler = Arc::new(handler);
+
+        let mut workers = Vec::with_capacity(size);
+        for id in 0..size {
+            workers.push(Worker::new(id, Arc::clone(&receiver), Arc::clone(&handler)));
+        }
+        ThreadPool {
+            workers,
+            sender: Some(sender),
+        }
+    }
+    /// queue the task `t` for the workers. Err(t) if the queue is full.
+    pub fn execute(&self, t: T) -> Result<(), T> {
+        match self.sender.as_ref() {
+            Some(s) => s.try_send(t).map_err(|e| match e {
+                mpsc::TrySendError::Full(t) => t,
+                mpsc::TrySendError::Disconnected(t) => t,
+            }),
+            None => Err(t),
+        }
+    }
+}
+
+impl<T: Send + 'static> Drop for ThreadPool<T> {
+    fn drop(&mut self) {
+        // workers stop when the channel is closed and the queue is drained.
+        drop(self.sender.take());
+        for w in self.workers.iter_mut() {
+            if let Some(t) = w.thread.take() {
+                if t.join().is_err() {
+                    eprintln!("worker {} panicked", w.id);
+                }
+            }
+        }
+    }
+}
+
+impl Worker {
+    fn new<T, F>(id: usize, receiver: Arc<Mutex<mpsc::Receiver<T>>>, handler: Arc<F>) -> Worker
+    where
+        T: Send + 'static,
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        let thread = thread::spawn(move || loop {
+            let t = match receiver.lock() {
+                Ok(r) => r.recv(),
+                Err(_) => break,
+            };
+            match t {
+                Ok(t) => {
+                    // keep the worker alive if one task panics.
+                    if panic::catch_unwind(AssertUnwindSafe(|| handler(t))).is_err() {
+                        eprintln!("worker {} task panicked", id);
+                    }
+                }
+                Err(_) => break,
+            }
+        });
+        Worker {
+            id,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// A value shared by all workers, which can be replaced while they are using it.
+/// a task keeps the value it got by `get()`, even after `set()` replaces it.
+pub struct Shared<T> {
+    cur: RwLock<Arc<T>>,
+}
+
+impl<T> Shared<T> {
+    pub fn new(t: T) -> Shared<T> {
+        Shared {
+            cur: RwLock::new(Arc::new(t)),
+        }
+    }
+    /// the current value.
+    pub fn get(&self) -> Arc<T> {
+        match self.cur.read() {
+            Ok(c) => Arc::clone(&c),
+            Err(e) => Arc::clone(&e.into_inner()),
+        }
+    }
+    /// replace the value, the old one is dropped when no task uses it.
+    pub fn set(&self, t: T) {
+        let t = Arc::new(t);
+        match self.cur.write() {
+            Ok(mut c) => *c = t,
+            Err(e) => *e.into_inner() = t,
+        }
+    }
+}